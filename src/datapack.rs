@@ -0,0 +1,104 @@
+use std::io::Write;
+use std::path::Path;
+
+use crate::program::Function;
+
+fn json_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn function_tag(functions: &[String]) -> String {
+    format!(
+        "{{\n  \"values\": [\n{}\n  ]\n}}\n",
+        functions
+            .iter()
+            .map(|f| format!("    {}", json_string(f)))
+            .collect::<Vec<String>>()
+            .join(",\n")
+    )
+}
+
+/// A data pack holding compiled functions, ready to be written as a directory or a zip.
+pub struct Datapack {
+    pub pack_format: u32,
+    pub description: String,
+    pub functions: Vec<Function>,
+    /// Functions tagged with `minecraft:load`, run when the pack is (re)loaded.
+    pub load: Vec<String>,
+    /// Functions tagged with `minecraft:tick`, run every tick.
+    pub tick: Vec<String>,
+}
+
+impl Datapack {
+    /// Pack format of Minecraft 1.21 and 1.21.1, the first versions with `function` directories.
+    pub const DEFAULT_PACK_FORMAT: u32 = 48;
+
+    pub fn new(pack_format: u32, description: impl Into<String>) -> Self {
+        Self {
+            pack_format,
+            description: description.into(),
+            functions: Vec::new(),
+            load: Vec::new(),
+            tick: Vec::new(),
+        }
+    }
+    /// Adds a setup function to the `minecraft:load` tag, running before anything else there.
+    /// When a function of the same name was compiled, the setup is put at its head instead.
+    pub fn setup(&mut self, setup: Function) {
+        let name = setup.name.clone();
+        match self.functions.iter_mut().find(|f| f.name == name) {
+            Some(function) => {
+                function.body.splice(0..0, setup.body);
+            }
+            None => self.functions.insert(0, setup),
+        }
+        self.load.retain(|function| *function != name);
+        self.load.insert(0, name);
+    }
+    /// Every file of the pack as a pair of its path inside the pack and its content.
+    pub fn files(&self) -> Vec<(String, String)> {
+        let mut files = vec![(
+            "pack.mcmeta".to_string(),
+            format!(
+                "{{\n  \"pack\": {{\n    \"pack_format\": {},\n    \"description\": {}\n  }}\n}}\n",
+                self.pack_format,
+                json_string(&self.description)
+            ),
+        )];
+        files.extend(
+            self.functions
+                .iter()
+                .map(|function| (function.path(), function.serialise() + "\n")),
+        );
+        for (tag, functions) in [("load", &self.load), ("tick", &self.tick)] {
+            if !functions.is_empty() {
+                files.push((
+                    format!("data/minecraft/tags/function/{}.json", tag),
+                    function_tag(functions),
+                ));
+            }
+        }
+        files
+    }
+    pub fn write_to_directory(&self, directory: &Path) -> std::io::Result<()> {
+        for (path, content) in self.files() {
+            let path = directory.join(path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, content)?;
+        }
+        Ok(())
+    }
+    pub fn write_to_zip(&self, path: &Path) -> std::io::Result<()> {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(path)?);
+        let options = zip::write::SimpleFileOptions::default();
+        for (path, content) in self.files() {
+            zip.start_file(path, options)
+                .map_err(std::io::Error::other)?;
+            zip.write_all(content.as_bytes())?;
+        }
+        zip.finish().map_err(std::io::Error::other)?;
+        Ok(())
+    }
+}
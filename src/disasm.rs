@@ -0,0 +1,233 @@
+use crate::{parser::parse_line, types::*};
+
+/// A line of a disassembled function.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Line {
+    Mnemonic(Box<Mnemonic>),
+    /// A `#` comment, kept as a `//` one.
+    Comment(String),
+    Blank,
+    /// A command with no mnemonic doing the same, kept as it is.
+    Raw(String),
+}
+
+impl std::fmt::Display for Line {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Mnemonic(mnemonic) => write!(f, "{}", mnemonic),
+            Self::Comment(comment) => write!(f, "//{}", comment),
+            Self::Blank => Ok(()),
+            Self::Raw(command) => write!(f, "{}", command),
+        }
+    }
+}
+
+/// Turns the commands of a function back into mnemonics, one line each. Only the shapes the compiler
+/// emits are recognised: `scoreboard players set`/`add`/`remove`/`operation`/`reset`,
+/// `scoreboard objectives add`, `NTS` and `STN` stores, `return run function` of a label,
+/// and any of them run by `execute if`/`unless score`, which become conditional jumps nested in order.
+/// A label is a block of `function`, the entry compiled from the source, e.g. `mcasm:main/loop` of `mcasm:main`.
+pub fn disassemble(mcfunction: &str, function: &str) -> Vec<Line> {
+    mcfunction
+        .lines()
+        .map(|line| {
+            let command = line.trim();
+            if command.is_empty() {
+                Line::Blank
+            } else if let Some(comment) = command.strip_prefix('#') {
+                Line::Comment(comment.to_string())
+            } else {
+                // A mnemonic is only given when its source means the same, so it has to parse back.
+                let words = command.split_whitespace().collect::<Vec<&str>>();
+                disassemble_command(&words, function)
+                    .filter(|mnemonic| {
+                        parse_line(0, &mnemonic.to_string())
+                            .is_ok_and(|parsed| parsed.to_string() == mnemonic.to_string())
+                    })
+                    .map(|mnemonic| Line::Mnemonic(Box::new(mnemonic)))
+                    .unwrap_or_else(|| Line::Raw(command.to_string()))
+            }
+        })
+        .collect()
+}
+
+fn disassemble_command(words: &[&str], function: &str) -> Option<Mnemonic> {
+    let score = |holder: &str, objective: &str| Scoreboard::new(holder, objective);
+    let int = |word: &str| {
+        word.parse::<i32>()
+            .ok()
+            .map(|value| IntLiteral::from(i64::from(value)))
+    };
+    Some(match words {
+        [
+            "scoreboard",
+            "objectives",
+            "add",
+            name,
+            criteria,
+            display_name @ ..,
+        ] => Mnemonic::Objective(Objective {
+            name: Token::new(*name, Span::default()),
+            criteria: criteria.to_string(),
+            display_name: (!display_name.is_empty()).then(|| display_name.join(" ")),
+        }),
+        ["scoreboard", "players", "set", holder, objective, value] => {
+            Mnemonic::Mov((score(holder, objective), Operand::Int(int(value)?)))
+        }
+        ["scoreboard", "players", "add", holder, objective, amount] => {
+            Mnemonic::Add((score(holder, objective), Operand::Int(int(amount)?)))
+        }
+        ["scoreboard", "players", "remove", holder, objective, amount] => {
+            Mnemonic::Sub((score(holder, objective), Operand::Int(int(amount)?)))
+        }
+        ["scoreboard", "players", "reset", holder, objective] => {
+            Mnemonic::Rel(score(holder, objective))
+        }
+        [
+            "scoreboard",
+            "players",
+            "operation",
+            holder,
+            objective,
+            operation,
+            source,
+            source_objective,
+        ] => {
+            let target = score(holder, objective);
+            let source = score(source, source_objective);
+            if *operation == "><" {
+                return Some(Mnemonic::Swp((target, source)));
+            }
+            let mnemonic = match *operation {
+                "=" => Mnemonic::Mov,
+                "+=" => Mnemonic::Add,
+                "-=" => Mnemonic::Sub,
+                "*=" => Mnemonic::Mul,
+                "/=" => Mnemonic::Div,
+                "%=" => Mnemonic::Sur,
+                "<" => Mnemonic::Min,
+                ">" => Mnemonic::Max,
+                _ => return None,
+            };
+            mnemonic((target, Operand::Score(source)))
+        }
+        [
+            "execute",
+            "store",
+            "result",
+            "score",
+            holder,
+            objective,
+            "run",
+            "data",
+            "get",
+            "storage",
+            storage,
+            path,
+            scale @ ..,
+        ] => {
+            let scale = match scale {
+                [] => 1,
+                [scale] => scale.parse::<u32>().ok()?,
+                _ => return None,
+            };
+            Mnemonic::Nts((
+                score(holder, objective),
+                Storage::try_from(&Token::new(*storage, Span::default())).ok()?,
+                Path::try_from(&Token::new(*path, Span::default())).ok()?,
+                scale,
+            ))
+        }
+        [
+            "execute",
+            "store",
+            "result",
+            "storage",
+            storage,
+            path,
+            storage_type,
+            scale,
+            "run",
+            "scoreboard",
+            "players",
+            "get",
+            holder,
+            objective,
+        ] => Mnemonic::Stn((
+            Storage::try_from(&Token::new(*storage, Span::default())).ok()?,
+            Path {
+                path: path.to_string(),
+                type_annotation: StorageType::try_from(storage_type)?,
+            },
+            score(holder, objective),
+            scale.parse::<f32>().ok()?,
+        )),
+        ["return", "run", "function", location] => {
+            let label = location.strip_prefix(function)?.strip_prefix('/')?;
+            Mnemonic::Jmp(Token::new(label, Span::default()))
+        }
+        [
+            "execute",
+            keyword @ ("if" | "unless"),
+            "score",
+            holder,
+            objective,
+            rest @ ..,
+        ] => {
+            let unless = *keyword == "unless";
+            let lhs = score(holder, objective);
+            let (comparison, rhs, rest) = match rest {
+                ["matches", range, rest @ ..] => {
+                    let (comparison, rhs) = match int(range) {
+                        Some(value) => (Comparison::Eq, Operand::Int(value)),
+                        None => {
+                            let token = Token::new(*range, Span::default());
+                            (
+                                Comparison::In,
+                                Operand::Range(RangeLiteral::try_from(&token).ok()??),
+                            )
+                        }
+                    };
+                    (comparison, rhs, rest)
+                }
+                [operator, holder, objective, rest @ ..] => {
+                    let comparison = match *operator {
+                        "=" => Comparison::Eq,
+                        "<" => Comparison::Lt,
+                        "<=" => Comparison::Le,
+                        ">" => Comparison::Gt,
+                        ">=" => Comparison::Ge,
+                        _ => return None,
+                    };
+                    (comparison, Operand::Score(score(holder, objective)), rest)
+                }
+                _ => return None,
+            };
+            // The conditions after this one guard the command along with it.
+            let guarded = match rest {
+                ["run", command @ ..] => disassemble_command(command, function)?,
+                ["if" | "unless", ..] => {
+                    disassemble_command(&[&["execute"], rest].concat(), function)?
+                }
+                _ => return None,
+            };
+            let jump = match (unless, comparison) {
+                (false, Comparison::Eq) => Mnemonic::Je,
+                (false, Comparison::Lt) => Mnemonic::Jl,
+                (false, Comparison::Le) => Mnemonic::Jle,
+                (false, Comparison::Gt) => Mnemonic::Jg,
+                (false, Comparison::Ge) => Mnemonic::Jge,
+                (false, Comparison::In) => Mnemonic::Jin,
+                (true, Comparison::Eq) => Mnemonic::Jne,
+                (true, Comparison::Lt) => Mnemonic::Jnl,
+                (true, Comparison::Le) => Mnemonic::Jnle,
+                (true, Comparison::Gt) => Mnemonic::Jng,
+                (true, Comparison::Ge) => Mnemonic::Jnge,
+                (true, Comparison::In) => Mnemonic::Jnin,
+            };
+            jump((lhs, rhs, Box::new(guarded)))
+        }
+        ["execute", "run", command @ ..] => disassemble_command(command, function)?,
+        _ => return None,
+    })
+}
@@ -1,12 +1,23 @@
+mod datapack;
+pub mod disasm;
+mod optimise;
 mod parser;
+mod program;
+mod setup;
+pub mod sim;
 mod types;
+pub mod visit;
 
-pub use parser::{parse, parse_line};
+pub use datapack::Datapack;
+pub use optimise::{fold, optimise};
+pub use parser::{parse, parse_line, tokenize};
+pub use program::{BasicBlock, ControlFlowGraph, Function, compile};
+pub use setup::Setup;
 pub use types::*;
 
 #[cfg(test)]
 mod tests {
-    use crate::types::{IntoSingleString, *};
+    use crate::types::IntoSingleString;
 
     use super::*;
 
@@ -17,13 +28,13 @@ mod tests {
         let b = Scoreboard::new("B", "mcasm");
         let c = Scoreboard::new("C", "mcasm");
 
-        const IDEAL_RESULT: &str = "scoreboard players operation A mcasm = B mcasm\nscoreboard players operation A mcasm += C mcasm\nscoreboard players set LITERAL_SCORE_CONVERSION MC_ASM 3\nscoreboard players operation A mcasm *= LITERAL_SCORE_CONVERSION MC_ASM";
+        const IDEAL_RESULT: &str = "scoreboard players operation A mcasm = B mcasm\nscoreboard players operation A mcasm += C mcasm\nscoreboard players set #r0 MC_ASM 3\nscoreboard players operation A mcasm *= #r0 MC_ASM";
 
         assert_eq!(
             [
-                Mnemonic::Mov((a.clone(), Box::new(b))),
-                Mnemonic::Add((a.clone(), Box::new(c))),
-                Mnemonic::Mul((a, Box::new(IntLiteral::from(3))))
+                Mnemonic::Mov((a.clone(), b.into())),
+                Mnemonic::Add((a.clone(), c.into())),
+                Mnemonic::Mul((a, IntLiteral::from(3).into()))
             ]
             .iter()
             .flat_map(|opecode| opecode.to_qualified().unwrap())
@@ -37,10 +48,10 @@ mod tests {
         let expected_result:String = "
             scoreboard players operation #A mcasm = #B mcasm
             scoreboard players operation #A mcasm += #C mcasm
-            scoreboard players set LITERAL_SCORE_CONVERSION MC_ASM 3
-            scoreboard players operation #A mcasm *= LITERAL_SCORE_CONVERSION MC_ASM
+            scoreboard players set #r0 MC_ASM 3
+            scoreboard players operation #A mcasm *= #r0 MC_ASM
             scoreboard players reset #A mcasm
-            execute store result score #D mcasm run data get mcasm:some path.to.data[0] 1024
+            execute store result score #D mcasm run data get storage mcasm:some path.to.data[0] 1024
             execute store result storage mcasm:some path.to.data[1] int 1 run scoreboard players get #D mcasm
         ".lines().map(|s| s.trim()).filter(|s| !s.is_empty()).collect::<Vec<&str>>().join("\n");
         let source = "
@@ -60,4 +71,1727 @@ mod tests {
             .into_single_string();
         assert_eq!(stringfied, expected_result);
     }
+
+    #[test]
+    fn parse_error_span_test() {
+        let source = "
+            MOV mcasm::#A 5
+            MOV mcasm:#A 5
+            MUL mcasm::#A
+        ";
+        let errors = parse(source).err().unwrap();
+        assert_eq!(errors.len(), 2);
+
+        assert_eq!(errors[0].span(), Span::new(2, 16, 24));
+        assert_eq!(
+            errors[0].to_string(),
+            "expected `objective::holder`, found `mcasm:#A`"
+        );
+        assert_eq!(errors[1].span(), Span::new(3, 12, 15));
+        assert_eq!(
+            errors[1].to_string(),
+            "`MUL` takes 2 operands, but 1 was given"
+        );
+
+        // Helper functions are placed under `mc_asm/`, which a label can't take.
+        let errors = parse("mc_asm/and:\nJMP mc_asm/and").unwrap_err();
+        assert_eq!(
+            errors.iter().map(MCAsmError::span).collect::<Vec<Span>>(),
+            [Span::new(0, 0, 10), Span::new(1, 4, 14)]
+        );
+        assert!(
+            matches!(&errors[0], MCAsmError::InvalidLabel(label) if label.text == "mc_asm/and")
+        );
+
+        // A lone mnemonic after a condition lacks its operands rather than being a label.
+        let errors = parse("JE mcasm::#a mcasm::#b REL").unwrap_err();
+        assert_eq!(errors[0].span(), Span::new(0, 23, 26));
+        assert_eq!(
+            errors[0].to_string(),
+            "`REL` takes 1 operand, but 0 were given"
+        );
+    }
+
+    #[test]
+    fn compile_blocks_test() {
+        let source = "
+            MOV mcasm::#i 0
+            loop:
+            ADD mcasm::#i 1
+            JL mcasm::#i mcasm::#n loop
+            JMP done
+            done:
+            REL mcasm::#n
+        ";
+        let functions =
+            compile(&parse(source).unwrap(), &mut Codegen::new("mcasm", "main")).unwrap();
+        let stringfied = functions
+            .iter()
+            .map(|function| format!("# {}\n{}", function.name, function.serialise()))
+            .collect::<Vec<String>>()
+            .join("\n");
+        let expected_result = "
+            # mcasm:main
+            scoreboard players set #i mcasm 0
+            function mcasm:main/loop
+            # mcasm:main/loop
+            scoreboard players add #i mcasm 1
+            execute if score #i mcasm < #n mcasm run return run function mcasm:main/loop
+            return run function mcasm:main/done
+            # mcasm:main/done
+            scoreboard players reset #n mcasm
+        "
+        .lines()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n");
+        assert_eq!(stringfied, expected_result);
+
+        let errors = compile(&parse("JMP nowhere").unwrap(), &mut Codegen::default())
+            .err()
+            .unwrap();
+        assert_eq!(errors[0].to_string(), "cannot find label `nowhere`");
+    }
+
+    #[test]
+    fn datapack_files_test() {
+        let mut datapack = Datapack::new(48, "Test");
+        datapack.functions = compile(
+            &parse("loop:\nADD mcasm::#i 1").unwrap(),
+            &mut Codegen::new("test", "main"),
+        )
+        .unwrap();
+        datapack.tick = vec!["test:main".to_string()];
+
+        let files = datapack.files();
+        assert_eq!(
+            files
+                .iter()
+                .map(|(path, _)| path.as_str())
+                .collect::<Vec<&str>>(),
+            [
+                "pack.mcmeta",
+                "data/test/function/main.mcfunction",
+                "data/test/function/main/loop.mcfunction",
+                "data/minecraft/tags/function/tick.json",
+            ]
+        );
+        assert_eq!(
+            files[0].1,
+            "{\n  \"pack\": {\n    \"pack_format\": 48,\n    \"description\": \"Test\"\n  }\n}\n"
+        );
+        assert_eq!(files[1].1, "function test:main/loop\n");
+        assert_eq!(
+            files[3].1,
+            "{\n  \"values\": [\n    \"test:main\"\n  ]\n}\n"
+        );
+    }
+
+    #[test]
+    fn setup_test() {
+        let source = "
+            .objective money dummy {\"text\":\"Money  and   more\"}
+            MOV money::@s mcasm::#A
+            NTS temp::#B mcasm:some path 1
+        ";
+        let mut setup = Setup::default();
+        setup.collect(&parse(source).unwrap()).unwrap();
+        // Objectives used only implicitly never override a declaration.
+        setup.collect(&parse("ADD money::@s 1").unwrap()).unwrap();
+        assert_eq!(
+            setup.commands().into_iter().into_single_string(),
+            [
+                "scoreboard objectives add MC_ASM dummy",
+                "scoreboard objectives add mcasm dummy",
+                "scoreboard objectives add money dummy {\"text\":\"Money  and   more\"}",
+                "scoreboard objectives add temp dummy",
+            ]
+            .join("\n")
+        );
+
+        let errors = setup
+            .collect(&parse(".objective money deathCount").unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(errors[0].span(), Span::new(0, 11, 16));
+
+        let errors = parse(
+            ".objective a_very_long_objective dummy\n.objective mon$y dummy\nJE mcasm::#a 1 .objective money dummy",
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>(),
+            [
+                "expected an objective name of up to 16 letters, digits, `_`, `-`, `.` or `+`, found `a_very_long_objective`",
+                "expected an objective name of up to 16 letters, digits, `_`, `-`, `.` or `+`, found `mon$y`",
+                "unknown mnemonic `.objective`",
+            ]
+        );
+    }
+
+    #[test]
+    fn constant_pool_test() {
+        let source = "
+            MUL mcasm::#A 3
+            DIV mcasm::#A 3
+            SUR mcasm::#B -2
+        ";
+        let mut codegen = Codegen {
+            constant_pool: true,
+            ..Default::default()
+        };
+        let mnemonics = parse(source).unwrap();
+        let functions = compile(&mnemonics, &mut codegen).unwrap();
+        assert_eq!(
+            functions[0].serialise(),
+            "scoreboard players operation #A mcasm *= #c3 MC_ASM\nscoreboard players operation #A mcasm /= #c3 MC_ASM\nscoreboard players operation #B mcasm %= #c-2 MC_ASM"
+        );
+
+        let mut setup = Setup::default();
+        setup.collect(&mnemonics).unwrap();
+        setup.collect_constants(&codegen);
+        assert_eq!(
+            setup.commands().into_iter().into_single_string(),
+            "scoreboard objectives add MC_ASM dummy\nscoreboard objectives add mcasm dummy\nscoreboard players set #c-2 MC_ASM -2\nscoreboard players set #c3 MC_ASM 3"
+        );
+    }
+
+    #[test]
+    fn literal_compare_test() {
+        let source = "
+            JE mcasm::#A 5 ADD mcasm::#R 1
+            JNE mcasm::#A 5 ADD mcasm::#R 1
+            JL mcasm::#A 5 ADD mcasm::#R 1
+            JLE mcasm::#A 5 ADD mcasm::#R 1
+            JG mcasm::#A -5 ADD mcasm::#R 1
+            JGE mcasm::#A -5 ADD mcasm::#R 1
+            JIN mcasm::#A 1..10 ADD mcasm::#R 1
+            JIN mcasm::#A ..10 ADD mcasm::#R 1
+            JGE mcasm::#A mcasm::#B ADD mcasm::#R 1
+        ";
+        let expected_result = "
+            execute if score #A mcasm matches 5 run scoreboard players add #R mcasm 1
+            execute unless score #A mcasm matches 5 run scoreboard players add #R mcasm 1
+            execute if score #A mcasm matches ..4 run scoreboard players add #R mcasm 1
+            execute if score #A mcasm matches ..5 run scoreboard players add #R mcasm 1
+            execute if score #A mcasm matches -4.. run scoreboard players add #R mcasm 1
+            execute if score #A mcasm matches -5.. run scoreboard players add #R mcasm 1
+            execute if score #A mcasm matches 1..10 run scoreboard players add #R mcasm 1
+            execute if score #A mcasm matches ..10 run scoreboard players add #R mcasm 1
+            execute if score #A mcasm >= #B mcasm run scoreboard players add #R mcasm 1
+        "
+        .lines()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n");
+        let stringfied = parse(source)
+            .unwrap()
+            .into_iter()
+            .flat_map(|mnemonic| mnemonic.to_qualified().unwrap())
+            .into_single_string();
+        assert_eq!(stringfied, expected_result);
+
+        let errors = parse("JIN mcasm::#A 5 ADD mcasm::#R 1").err().unwrap();
+        assert_eq!(
+            errors[0].to_string(),
+            "expected a range like `1..10`, `..4` or `mcasm::#lo..mcasm::#hi`, found `5`"
+        );
+        let errors = parse("JIN mcasm::#A 10..1 ADD mcasm::#R 1").err().unwrap();
+        assert_eq!(
+            errors[0].to_string(),
+            "expected a range whose minimum isn't above its maximum, found `10..1`"
+        );
+        assert_eq!(errors[0].span(), Span::new(0, 14, 19));
+    }
+
+    #[test]
+    fn negated_compare_test() {
+        let source = "
+            JNL mcasm::#A 5 ADD mcasm::#R 1
+            JNGE mcasm::#A mcasm::#B ADD mcasm::#R 1
+            JNIN mcasm::#A 1..10 ADD mcasm::#R 1
+            JIN mcasm::#A mcasm::#L..10 ADD mcasm::#R 1
+            JNIN mcasm::#A mcasm::#L..mcasm::#H ADD mcasm::#R 1
+            JNIN mcasm::#A ..mcasm::#H ADD mcasm::#R 1
+        ";
+        let expected_result = "
+            execute unless score #A mcasm matches ..4 run scoreboard players add #R mcasm 1
+            execute unless score #A mcasm >= #B mcasm run scoreboard players add #R mcasm 1
+            execute unless score #A mcasm matches 1..10 run scoreboard players add #R mcasm 1
+            execute if score #A mcasm >= #L mcasm if score #A mcasm matches ..10 run scoreboard players add #R mcasm 1
+            scoreboard players set #r0 MC_ASM 0
+            execute if score #A mcasm >= #L mcasm if score #A mcasm <= #H mcasm run scoreboard players set #r0 MC_ASM 1
+            execute if score #r0 MC_ASM matches 0 run scoreboard players add #R mcasm 1
+            execute unless score #A mcasm <= #H mcasm run scoreboard players add #R mcasm 1
+        "
+        .lines()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n");
+        let stringfied = parse(source)
+            .unwrap()
+            .into_iter()
+            .flat_map(|mnemonic| mnemonic.to_qualified().unwrap())
+            .into_single_string();
+        assert_eq!(stringfied, expected_result);
+    }
+
+    #[test]
+    fn compound_condition_test() {
+        let source = "
+            IF mcasm::#a == 1 && mcasm::#b > mcasm::#c THEN ADD mcasm::#r 1
+            IF mcasm::#a != mcasm::#b || mcasm::#c in 1..10 && mcasm::#d <= 0 THEN end
+            IF mcasm::#a < 1 ADD mcasm::#r 1
+            IF mcasm::#a = 1 THEN ADD mcasm::#r 1
+            IF mcasm::#a == 1 THEN
+        ";
+        let errors = parse(source).err().unwrap();
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>(),
+            [
+                "expected `&&`, `||` or `THEN`, found `ADD`",
+                "expected a comparison operator like `==`, `<` or `in`, found `=`",
+                "`IF` takes 5 operands, but 4 were given",
+            ]
+        );
+
+        let expected_result = "
+            execute if score #a mcasm matches 1 if score #b mcasm > #c mcasm run scoreboard players add #r mcasm 1
+            scoreboard players set #r0 MC_ASM 0
+            execute unless score #a mcasm = #b mcasm run scoreboard players set #r0 MC_ASM 1
+            execute if score #c mcasm matches 1..10 if score #d mcasm matches ..0 run scoreboard players set #r0 MC_ASM 1
+            execute if score #r0 MC_ASM matches 1 run return run function mcasm:main/end
+        "
+        .lines()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n");
+        let stringfied = parse(&source.lines().take(3).collect::<Vec<&str>>().join("\n"))
+            .unwrap()
+            .into_iter()
+            .flat_map(|mnemonic| mnemonic.to_qualified().unwrap())
+            .into_single_string();
+        assert_eq!(stringfied, expected_result);
+
+        // The inner condition is stored into a flag apart from the outer one's.
+        let nested = "IF mcasm::#a == 1 || mcasm::#b == 1 THEN IF mcasm::#c == 1 || mcasm::#d == 1 THEN ADD mcasm::#r 1";
+        let expected_result = "
+            scoreboard players set #r0 MC_ASM 0
+            execute if score #a mcasm matches 1 run scoreboard players set #r0 MC_ASM 1
+            execute if score #b mcasm matches 1 run scoreboard players set #r0 MC_ASM 1
+            execute if score #r0 MC_ASM matches 1 run scoreboard players set #r2 MC_ASM 0
+            execute if score #r0 MC_ASM matches 1 if score #c mcasm matches 1 run scoreboard players set #r2 MC_ASM 1
+            execute if score #r0 MC_ASM matches 1 if score #d mcasm matches 1 run scoreboard players set #r2 MC_ASM 1
+            execute if score #r0 MC_ASM matches 1 if score #r2 MC_ASM matches 1 run scoreboard players add #r mcasm 1
+        "
+        .lines()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n");
+        let stringfied = parse(nested)
+            .unwrap()
+            .into_iter()
+            .flat_map(|mnemonic| mnemonic.to_qualified().unwrap())
+            .into_single_string();
+        assert_eq!(stringfied, expected_result);
+
+        // A negated `in` is written back as it's parsed.
+        let negated = "IF mcasm::#a !in 1..10 || mcasm::#b != 0 THEN ADD mcasm::#r 1";
+        let mnemonics = parse(negated).unwrap();
+        assert_eq!(mnemonics[0].to_string(), negated);
+        assert_eq!(
+            mnemonics[0]
+                .to_qualified()
+                .unwrap()
+                .into_iter()
+                .into_single_string(),
+            "\
+scoreboard players set #r0 MC_ASM 0
+execute unless score #a mcasm matches 1..10 run scoreboard players set #r0 MC_ASM 1
+execute unless score #b mcasm matches 0 run scoreboard players set #r0 MC_ASM 1
+execute if score #r0 MC_ASM matches 1 run scoreboard players add #r mcasm 1"
+        );
+    }
+
+    #[test]
+    fn execute_modifier_test() {
+        let source = "
+            AS @a[tag=x, limit=1] AT @s ADD mcasm::@s 1
+            POSITIONED ~ ~1 ~ IN minecraft:the_nether IF ENTITY @e[type=pig] JMP found
+            UNLESS BLOCK ~ ~-1 ~ minecraft:stone JE mcasm::#a 1 MOV mcasm::#b 2
+            IF DATA STORAGE mcasm:s {flag:1b} STORE RESULT mcasm::#r MOV mcasm::#r mcasm::#a
+            IF DATA ENTITY @s Inventory[0] STORE SUCCESS mcasm:s out::<byte> 1 MOV mcasm::#r 1
+        ";
+        let expected_result = "
+            execute as @a[tag=x, limit=1] at @s run scoreboard players add @s mcasm 1
+            execute positioned ~ ~1 ~ in minecraft:the_nether if entity @e[type=pig] run return run function mcasm:main/found
+            execute unless block ~ ~-1 ~ minecraft:stone if score #a mcasm matches 1 run scoreboard players set #b mcasm 2
+            execute if data storage mcasm:s {flag:1b} store result score #r mcasm run scoreboard players operation #r mcasm = #a mcasm
+            execute if data entity @s Inventory[0] store success storage mcasm:s out byte 1 run scoreboard players set #r mcasm 1
+        "
+        .lines()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n");
+        let stringfied = parse(source)
+            .unwrap()
+            .into_iter()
+            .flat_map(|mnemonic| mnemonic.to_qualified().unwrap())
+            .into_single_string();
+        assert_eq!(stringfied, expected_result);
+
+        let errors = parse("UNLESS SCORE @s\nAS @a[tag=x\nSTORE MAX mcasm::#r ADD mcasm::#r 1")
+            .err()
+            .unwrap();
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>(),
+            [
+                "expected `ENTITY`, `BLOCK` or `DATA`, found `SCORE`",
+                "expected `]` in the selector, found `@a[tag=x`",
+                "expected `RESULT` or `SUCCESS`, found `MAX`",
+            ]
+        );
+    }
+
+    #[test]
+    fn score_holder_test() {
+        let tokens = tokenize(0, r#"MOV mcasm::@e[tag=a, name="b c", nbt={x:[1, 2]}] 1"#);
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|t| t.text.as_str())
+                .collect::<Vec<&str>>(),
+            [
+                "MOV",
+                r#"mcasm::@e[tag=a, name="b c", nbt={x:[1, 2]}]"#,
+                "1"
+            ]
+        );
+        let holder =
+            |text: &str| ScoreHolder::parse(&Token::new(text, Span::new(0, 0, text.len())));
+        assert_eq!(holder("*"), Ok(ScoreHolder::Wildcard));
+        assert_eq!(holder("#x"), Ok(ScoreHolder::FakePlayer("#x".into())));
+        assert_eq!(holder("Steve"), Ok(ScoreHolder::Player("Steve".into())));
+        assert_eq!(
+            holder("@e[ type=zombie ,tag=!boss]").map(|h| h.to_string()),
+            Ok("@e[type=zombie,tag=!boss]".to_string())
+        );
+
+        let source = "
+            MOV mcasm::@e[type=zombie 1
+            MOV mcasm::@x 1
+            MOV mcasm::@e[colour=red] 1
+            MOV mcasm::@e[limit=] 1
+            MOV mcasm::@e[name=\"a] 1
+            MOV mcasm::# 1
+        ";
+        let errors = parse(source).err().unwrap();
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>(),
+            [
+                "expected `]` in the selector, found `@e[type=zombie`",
+                "expected one of `@p`, `@a`, `@r`, `@s`, `@e` or `@n` in the selector, found `@x`",
+                "expected a selector argument like `tag` or `type` in the selector, found `colour`",
+                "expected a value in the selector, found `limit=`",
+                "expected a closing quote in the selector, found `\"a`",
+                "expected a name, `#name`, `*` or a selector as a score holder, found `#`",
+            ]
+        );
+        assert_eq!(errors[2].span(), Span::new(3, 26, 32));
+    }
+
+    #[test]
+    fn fixed_point_test() {
+        let source = "
+            .fixed mcasm::#x 1000
+            .fixed mcasm::#y 10
+            MOV mcasm::#x 1.5
+            ADD mcasm::#x 2
+            MUL mcasm::#x 1.5
+            DIV mcasm::#x 0.25
+            MUL mcasm::#x mcasm::#y
+            ADD mcasm::#x mcasm::#y
+            MOV mcasm::#i mcasm::#x
+            NTS mcasm::#x mcasm:s value 1
+            STN mcasm:s value::<double> mcasm::#x 1
+            JL mcasm::#x 3 ADD mcasm::#x 1
+        ";
+        let functions = compile(&parse(source).unwrap(), &mut Codegen::default()).unwrap();
+        let expected_result = "
+            scoreboard players set #x mcasm 1500
+            scoreboard players add #x mcasm 2000
+            scoreboard players operation #x mcasm *= #c3 MC_ASM
+            scoreboard players operation #x mcasm /= #c2 MC_ASM
+            scoreboard players operation #x mcasm *= #c4 MC_ASM
+            scoreboard players operation #x mcasm *= #y mcasm
+            scoreboard players operation #x mcasm /= #c10 MC_ASM
+            scoreboard players operation #r0 MC_ASM = #y mcasm
+            scoreboard players operation #r0 MC_ASM *= #c100 MC_ASM
+            scoreboard players operation #x mcasm += #r0 MC_ASM
+            scoreboard players operation #i mcasm = #x mcasm
+            scoreboard players operation #i mcasm /= #c1000 MC_ASM
+            execute store result score #x mcasm run data get storage mcasm:s value 1000
+            execute store result storage mcasm:s value double 0.001 run scoreboard players get #x mcasm
+            execute if score #x mcasm matches ..2999 run scoreboard players add #x mcasm 1000
+        "
+        .lines()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n");
+        assert_eq!(functions[0].serialise(), expected_result);
+
+        let errors = compile(
+            &parse(".fixed mcasm::#x 10\n.fixed mcasm::#x 100\nMOV mcasm::#x 0.25").unwrap(),
+            &mut Codegen::default(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(errors[0].to_string(), "the scale is already declared as 10");
+        let errors = compile(
+            &parse("MOV mcasm::#x 0.5").unwrap(),
+            &mut Codegen::default(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            errors[0].to_string(),
+            "`0.5` can't be represented with a fixed-point scale of 1, declare a finer one with `.fixed`"
+        );
+        let errors = compile(
+            &parse(".fixed mcasm::#x 1000\nNTS mcasm::#x mcasm:s value 5000000").unwrap(),
+            &mut Codegen::default(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            errors[0].to_string(),
+            "the magnification 5000000 times the fixed-point scale of 1000 doesn't fit into an unsigned 32-bit integer"
+        );
+
+        // Scores of different scales are compared by their values, e.g. `1.5` and `2` rather than 150 and 2.
+        let source = "
+            .fixed mcasm::#f 100
+            .fixed mcasm::#g 40
+            MOV mcasm::#f 1.51
+            MOV mcasm::#g 1.525
+            MOV mcasm::#a 2
+            MOV mcasm::#lo 1
+            JL mcasm::#f mcasm::#a MOV mcasm::#less 1
+            JE mcasm::#a mcasm::#f MOV mcasm::#equal 1
+            JIN mcasm::#f mcasm::#lo..mcasm::#a MOV mcasm::#within 1
+            JL mcasm::#f mcasm::#g MOV mcasm::#finer 1
+            JGE mcasm::#f mcasm::#g MOV mcasm::#finer 2
+            JNL mcasm::#f mcasm::#unset MOV mcasm::#unset_less 1
+        ";
+        let mnemonics = parse(source).unwrap();
+        let functions = compile(&mnemonics, &mut Codegen::default()).unwrap();
+        let mut machine = sim::Machine::new();
+        sim::Interpreter::new(&functions)
+            .call("mcasm:main", &mut machine)
+            .unwrap();
+        let score = |holder: &str| machine.score(&Scoreboard::new(holder, "mcasm"));
+        assert_eq!(
+            [
+                "#less",
+                "#equal",
+                "#within",
+                "#finer",
+                "#unset_less",
+                "#unset"
+            ]
+            .map(score),
+            [Some(1), None, Some(1), Some(1), Some(1), None]
+        );
+        let mut simulated = sim::Machine::new();
+        simulated.run(&mnemonics, &mut Codegen::default()).unwrap();
+        machine.scores.remove("MC_ASM");
+        assert_eq!(simulated.dump(), machine.dump());
+
+        let errors = compile(
+            &parse(".fixed mcasm::#x 65536\n.fixed mcasm::#y 65535\nJE mcasm::#x mcasm::#y JMP end\nend:")
+                .unwrap(),
+            &mut Codegen::default(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            errors[0].to_string(),
+            "scores of the fixed-point scales 65536 and 65535 can't be compared within 32 bits"
+        );
+        assert_eq!(errors[0].span(), Span::new(2, 13, 22));
+    }
+
+    #[test]
+    fn long_register_test() {
+        let source = "
+            .long mcasm::#a
+            MOV mcasm::#a 4294967296
+            ADD mcasm::#a mcasm::#i
+            JL mcasm::#a 0 JMP negative
+            negative:
+        ";
+        let functions = compile(&parse(source).unwrap(), &mut Codegen::default()).unwrap();
+        let expected_result = "
+            scoreboard players operation #a.lo mcasm = #c0 MC_ASM
+            scoreboard players operation #a.hi mcasm = #c1 MC_ASM
+            scoreboard players set #r0 MC_ASM 0
+            execute if score #i mcasm matches ..-1 run scoreboard players set #r0 MC_ASM -1
+            scoreboard players operation #r1 MC_ASM = #a.lo mcasm
+            scoreboard players operation #r1 MC_ASM += #c-2147483648 MC_ASM
+            scoreboard players operation #a.lo mcasm += #i mcasm
+            scoreboard players operation #r2 MC_ASM = #a.lo mcasm
+            scoreboard players operation #r2 MC_ASM += #c-2147483648 MC_ASM
+            scoreboard players operation #a.hi mcasm += #r0 MC_ASM
+            execute if score #r2 MC_ASM < #r1 MC_ASM run scoreboard players add #a.hi mcasm 1
+            execute if score #a.lo mcasm matches -2147483648.. run scoreboard players operation #r1 MC_ASM = #a.lo mcasm
+            execute if score #a.lo mcasm matches -2147483648.. run scoreboard players operation #r1 MC_ASM += #c-2147483648 MC_ASM
+            scoreboard players operation #r2 MC_ASM = #c0 MC_ASM
+            scoreboard players operation #r2 MC_ASM += #c-2147483648 MC_ASM
+            scoreboard players set #r4 MC_ASM 0
+            execute if score #a.lo mcasm matches -2147483648.. if score #a.hi mcasm < #c0 MC_ASM run scoreboard players set #r4 MC_ASM 1
+            execute if score #a.hi mcasm = #c0 MC_ASM if score #a.lo mcasm matches -2147483648.. if score #r1 MC_ASM < #r2 MC_ASM run scoreboard players set #r4 MC_ASM 1
+            execute if score #r4 MC_ASM matches 1 run return run function mcasm:main/negative
+            function mcasm:main/negative
+        "
+        .lines()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n");
+        assert_eq!(functions[0].serialise(), expected_result);
+
+        let errors = compile(
+            &parse(".long mcasm::#a\nDIV mcasm::#a 2\nADD mcasm::#i 5000000000").unwrap(),
+            &mut Codegen::default(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>(),
+            [
+                "`DIV` isn't supported with long registers",
+                "`5000000000` doesn't fit into a 32-bit score",
+            ]
+        );
+        assert_eq!(errors[1].span(), Span::new(2, 14, 24));
+    }
+
+    #[test]
+    fn literal_range_test() {
+        let source = "
+            MOV mcasm::#x 2147483647
+            MOV mcasm::#x -2147483648
+            ADD mcasm::#x -5
+            SUB mcasm::#x -5
+            ADD mcasm::#x -2147483648
+            SUB mcasm::#x -2147483648
+            JL mcasm::#x -2147483648 ADD mcasm::#r 1
+            JG mcasm::#x 2147483647 ADD mcasm::#r 1
+            JGE mcasm::#x 2147483647 ADD mcasm::#r 1
+        ";
+        let expected_result = "
+            scoreboard players set #x mcasm 2147483647
+            scoreboard players set #x mcasm -2147483648
+            scoreboard players remove #x mcasm 5
+            scoreboard players add #x mcasm 5
+            scoreboard players set #r0 MC_ASM -2147483648
+            scoreboard players operation #x mcasm += #r0 MC_ASM
+            scoreboard players set #r0 MC_ASM -2147483648
+            scoreboard players operation #x mcasm += #r0 MC_ASM
+            execute if score #x mcasm < #x mcasm run scoreboard players add #r mcasm 1
+            execute if score #x mcasm < #x mcasm run scoreboard players add #r mcasm 1
+            execute if score #x mcasm matches 2147483647.. run scoreboard players add #r mcasm 1
+        "
+        .lines()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n");
+        let functions = compile(&parse(source).unwrap(), &mut Codegen::default()).unwrap();
+        assert_eq!(functions[0].serialise(), expected_result);
+
+        let source = "MOV mcasm::#x 2147483648\nADD mcasm::#x -2147483649\nJIN mcasm::#x 1..4294967296 JMP end\nend:";
+        let errors = compile(&parse(source).unwrap(), &mut Codegen::default())
+            .err()
+            .unwrap();
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>(),
+            [
+                "`2147483648` doesn't fit into a 32-bit score",
+                "`-2147483649` doesn't fit into a 32-bit score",
+                "`4294967296` doesn't fit into a 32-bit score",
+            ]
+        );
+        assert_eq!(errors[0].span(), Span::new(0, 14, 24));
+
+        // Scaling by `.fixed` overflows 64 bits before narrowing, which mustn't wrap back into range.
+        let source = ".fixed mcasm::#f 4\nMOV mcasm::#f 4611686018427387904\nJE mcasm::#f 4611686018427387904 JMP end\nJIN mcasm::#f 4611686018427387904.. JMP end\nend:";
+        let errors = compile(&parse(source).unwrap(), &mut Codegen::default())
+            .err()
+            .unwrap();
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>(),
+            ["`4611686018427387904` doesn't fit into a 32-bit score"; 3]
+        );
+
+        let mut codegen = Codegen {
+            allow_wrapping: true,
+            ..Default::default()
+        };
+        let functions = compile(&parse("MOV mcasm::#x 4294967297").unwrap(), &mut codegen).unwrap();
+        assert_eq!(
+            functions[0].serialise(),
+            "scoreboard players set #x mcasm 1"
+        );
+    }
+
+    #[test]
+    fn integer_literal_forms_test() {
+        let source = "
+            MOV mcasm::#x 0xFF
+            MOV mcasm::#x -0x80000000
+            MOV mcasm::#x 0b1010
+            MOV mcasm::#x 1_000_000
+            MOV mcasm::#x 'A'
+            MOV mcasm::#x ' '
+            MOV mcasm::#x '\\''
+            JIN mcasm::#x 0x10..0b11111 ADD mcasm::#r 1
+        ";
+        let expected_result = "
+            scoreboard players set #x mcasm 255
+            scoreboard players set #x mcasm -2147483648
+            scoreboard players set #x mcasm 10
+            scoreboard players set #x mcasm 1000000
+            scoreboard players set #x mcasm 65
+            scoreboard players set #x mcasm 32
+            scoreboard players set #x mcasm 39
+            execute if score #x mcasm matches 16..31 run scoreboard players add #r mcasm 1
+        "
+        .lines()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n");
+        let stringfied = parse(source)
+            .unwrap()
+            .into_iter()
+            .flat_map(|mnemonic| mnemonic.to_qualified().unwrap())
+            .into_single_string();
+        assert_eq!(stringfied, expected_result);
+
+        for invalid in ["0x", "0xG", "_1", "1_", "0b102", "''", "'AB'", "--1"] {
+            let errors = parse(&format!("MOV mcasm::#x {}", invalid)).err().unwrap();
+            assert_eq!(
+                errors[0].to_string(),
+                format!(
+                    "expected `objective::holder` or a number, found `{}`",
+                    invalid
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn bitwise_test() {
+        let source = "
+            AND mcasm::#x 0xFF
+            XOR mcasm::#x mcasm::#y
+            OR mcasm::#x 6
+            NOT mcasm::#x
+            SHL mcasm::#x 4
+            SHR mcasm::#x 31
+            SHR mcasm::#x mcasm::#n
+        ";
+        let functions = compile(&parse(source).unwrap(), &mut Codegen::default()).unwrap();
+        let join = |s: &str| {
+            s.lines()
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .collect::<Vec<&str>>()
+                .join("\n")
+        };
+        assert_eq!(
+            functions
+                .iter()
+                .map(|function| function.name.as_str())
+                .collect::<Vec<&str>>(),
+            [
+                "mcasm:main",
+                "mcasm:main/mc_asm/or",
+                "mcasm:main/mc_asm/shr",
+                "mcasm:main/mc_asm/xor",
+            ]
+        );
+        let expected_result = "
+            scoreboard players operation #x mcasm %= #c256 MC_ASM
+            scoreboard players operation #bitwise_lhs MC_ASM = #x mcasm
+            scoreboard players operation #bitwise_rhs MC_ASM = #y mcasm
+            scoreboard players set #bitwise_result MC_ASM 0
+            scoreboard players set #bitwise_weight MC_ASM 1
+            function mcasm:main/mc_asm/xor
+            scoreboard players operation #x mcasm = #bitwise_result MC_ASM
+            scoreboard players operation #bitwise_lhs MC_ASM = #x mcasm
+            scoreboard players set #bitwise_rhs MC_ASM 6
+            scoreboard players set #bitwise_result MC_ASM 0
+            scoreboard players set #bitwise_weight MC_ASM 1
+            function mcasm:main/mc_asm/or
+            scoreboard players operation #x mcasm = #bitwise_result MC_ASM
+            scoreboard players set #bitwise_lhs MC_ASM -1
+            scoreboard players operation #bitwise_lhs MC_ASM -= #x mcasm
+            scoreboard players operation #x mcasm = #bitwise_lhs MC_ASM
+            scoreboard players operation #x mcasm *= #c16 MC_ASM
+            scoreboard players operation #bitwise_lhs MC_ASM = #x mcasm
+            scoreboard players operation #bitwise_lhs MC_ASM /= #c1073741824 MC_ASM
+            scoreboard players operation #bitwise_lhs MC_ASM /= #c2 MC_ASM
+            scoreboard players operation #x mcasm = #bitwise_lhs MC_ASM
+            scoreboard players operation #bitwise_lhs MC_ASM = #x mcasm
+            scoreboard players operation #bitwise_rhs MC_ASM = #n mcasm
+            execute if score #bitwise_rhs MC_ASM matches 33.. run scoreboard players set #bitwise_rhs MC_ASM 32
+            execute if score #bitwise_rhs MC_ASM matches 1.. run function mcasm:main/mc_asm/shr
+            scoreboard players operation #x mcasm = #bitwise_lhs MC_ASM
+        ";
+        assert_eq!(functions[0].serialise(), join(expected_result));
+        let expected_result = "
+            scoreboard players operation #bitwise_bits MC_ASM = #bitwise_lhs MC_ASM
+            scoreboard players operation #bitwise_bits MC_ASM %= #c2 MC_ASM
+            scoreboard players operation #bitwise_bit MC_ASM = #bitwise_rhs MC_ASM
+            scoreboard players operation #bitwise_bit MC_ASM %= #c2 MC_ASM
+            scoreboard players operation #bitwise_bits MC_ASM += #bitwise_bit MC_ASM
+            execute if score #bitwise_bits MC_ASM matches 1 run scoreboard players operation #bitwise_result MC_ASM += #bitwise_weight MC_ASM
+            scoreboard players operation #bitwise_lhs MC_ASM /= #c2 MC_ASM
+            scoreboard players operation #bitwise_rhs MC_ASM /= #c2 MC_ASM
+            scoreboard players operation #bitwise_weight MC_ASM += #bitwise_weight MC_ASM
+            execute unless score #bitwise_weight MC_ASM matches 0 run function mcasm:main/mc_asm/xor
+        ";
+        assert_eq!(functions[3].serialise(), join(expected_result));
+        let expected_result = "
+            scoreboard players operation #bitwise_lhs MC_ASM /= #c2 MC_ASM
+            scoreboard players remove #bitwise_rhs MC_ASM 1
+            execute if score #bitwise_rhs MC_ASM matches 1.. run function mcasm:main/mc_asm/shr
+        ";
+        assert_eq!(functions[2].serialise(), join(expected_result));
+
+        let errors = parse("SHL mcasm::#x -1").err().unwrap();
+        assert_eq!(
+            errors[0].to_string(),
+            "expected `objective::holder` or an amount from 0, found `-1`"
+        );
+        let errors = compile(
+            &parse(".long mcasm::#a\nAND mcasm::#a 3").unwrap(),
+            &mut Codegen::default(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            errors[0].to_string(),
+            "`AND` isn't supported with long registers"
+        );
+    }
+
+    #[test]
+    fn min_max_swap_test() {
+        let source = "
+            .fixed mcasm::#f 10
+            MIN mcasm::#x 100
+            MAX mcasm::#x mcasm::#y
+            MAX mcasm::#f 0.5
+            MIN mcasm::#f mcasm::#x
+            SWP mcasm::#x mcasm::#y
+            SWP mcasm::#f mcasm::#x
+            NEG mcasm::#x
+            ABS mcasm::#x
+        ";
+        let functions = compile(&parse(source).unwrap(), &mut Codegen::default()).unwrap();
+        let expected_result = "
+            scoreboard players set #r0 MC_ASM 100
+            scoreboard players operation #x mcasm < #r0 MC_ASM
+            scoreboard players operation #x mcasm > #y mcasm
+            scoreboard players set #r0 MC_ASM 5
+            scoreboard players operation #f mcasm > #r0 MC_ASM
+            scoreboard players operation #r0 MC_ASM = #x mcasm
+            scoreboard players operation #r0 MC_ASM *= #c10 MC_ASM
+            scoreboard players operation #f mcasm < #r0 MC_ASM
+            scoreboard players operation #x mcasm >< #y mcasm
+            scoreboard players operation #f mcasm >< #x mcasm
+            scoreboard players operation #f mcasm *= #c10 MC_ASM
+            scoreboard players operation #x mcasm /= #c10 MC_ASM
+            scoreboard players set #r0 MC_ASM -1
+            scoreboard players operation #x mcasm *= #r0 MC_ASM
+            scoreboard players set #r0 MC_ASM -1
+            execute if score #x mcasm matches ..-1 run scoreboard players operation #x mcasm *= #r0 MC_ASM
+        "
+        .lines()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n");
+        assert_eq!(functions[0].serialise(), expected_result);
+
+        let mut codegen = Codegen {
+            constant_pool: true,
+            ..Codegen::default()
+        };
+        let functions = compile(&parse("NEG mcasm::#x").unwrap(), &mut codegen).unwrap();
+        assert_eq!(
+            functions[0].serialise(),
+            "scoreboard players operation #x mcasm *= #c-1 MC_ASM"
+        );
+
+        let errors = parse("SWP mcasm::#x 1").err().unwrap();
+        assert_eq!(
+            errors[0].to_string(),
+            "expected `objective::holder`, found `1`"
+        );
+        let errors = compile(
+            &parse(".long mcasm::#a\nMAX mcasm::#a 3\nNEG mcasm::#a").unwrap(),
+            &mut Codegen::default(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>(),
+            [
+                "`MAX` isn't supported with long registers",
+                "`NEG` isn't supported with long registers",
+            ]
+        );
+    }
+
+    #[test]
+    fn expression_test() {
+        let source = "
+            LET mcasm::#A = (mcasm::#B + mcasm::#C) * 3 - mcasm::#D / 2
+            LET mcasm::#A = mcasm::#B - mcasm::#A
+            LET mcasm::#A = - (mcasm::#A + 1)
+            LET mcasm::#A = mcasm::#B * (mcasm::#C - (mcasm::#D % 4))
+        ";
+        let expected_result = "
+            scoreboard players operation #A mcasm = #B mcasm
+            scoreboard players operation #A mcasm += #C mcasm
+            scoreboard players set #r1 MC_ASM 3
+            scoreboard players operation #A mcasm *= #r1 MC_ASM
+            scoreboard players operation #r0 MC_ASM = #D mcasm
+            scoreboard players set #r1 MC_ASM 2
+            scoreboard players operation #r0 MC_ASM /= #r1 MC_ASM
+            scoreboard players operation #A mcasm -= #r0 MC_ASM
+            scoreboard players reset #r0 MC_ASM
+            scoreboard players operation #r0 MC_ASM = #B mcasm
+            scoreboard players operation #r0 MC_ASM -= #A mcasm
+            scoreboard players operation #A mcasm = #r0 MC_ASM
+            scoreboard players reset #r0 MC_ASM
+            scoreboard players add #A mcasm 1
+            scoreboard players set #r0 MC_ASM -1
+            scoreboard players operation #A mcasm *= #r0 MC_ASM
+            scoreboard players operation #A mcasm = #B mcasm
+            scoreboard players operation #r0 MC_ASM = #C mcasm
+            scoreboard players operation #r1 MC_ASM = #D mcasm
+            scoreboard players set #r2 MC_ASM 4
+            scoreboard players operation #r1 MC_ASM %= #r2 MC_ASM
+            scoreboard players operation #r0 MC_ASM -= #r1 MC_ASM
+            scoreboard players operation #A mcasm *= #r0 MC_ASM
+            scoreboard players reset #r0 MC_ASM
+            scoreboard players reset #r1 MC_ASM
+        "
+        .lines()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n");
+        let stringfied = parse(source)
+            .unwrap()
+            .into_iter()
+            .flat_map(|mnemonic| mnemonic.to_qualified().unwrap())
+            .into_single_string();
+        assert_eq!(stringfied, expected_result);
+
+        for (source, message, span) in [
+            (
+                "LET mcasm::#A = (mcasm::#B + 1",
+                "expected `)` closing this, found `(`",
+                Span::new(0, 16, 17),
+            ),
+            (
+                "LET mcasm::#A = mcasm::#B +",
+                "expected an operand after it, found `+`",
+                Span::new(0, 26, 27),
+            ),
+            (
+                "LET mcasm::#A = mcasm::#B mcasm::#C",
+                "expected an operator like `+`, found `mcasm::#C`",
+                Span::new(0, 26, 35),
+            ),
+            (
+                "LET mcasm::#A mcasm::#B",
+                "expected `=`, found `mcasm::#B`",
+                Span::new(0, 14, 23),
+            ),
+        ] {
+            let errors = parse(source).err().unwrap();
+            assert_eq!(errors[0].to_string(), message);
+            assert_eq!(errors[0].span(), span);
+        }
+    }
+
+    #[test]
+    fn register_allocation_test() {
+        let source = "
+            JNIN mcasm::#x mcasm::#lo..mcasm::#hi MUL mcasm::#x 3
+            IF mcasm::#x != mcasm::#y || mcasm::#z in mcasm::#lo..mcasm::#hi THEN MUL mcasm::#x 5
+            MUL mcasm::#x 7
+        ";
+        let expected_result = "
+            scoreboard players set #r0 tmp 0
+            execute if score #x mcasm >= #lo mcasm if score #x mcasm <= #hi mcasm run scoreboard players set #r0 tmp 1
+            execute if score #r0 tmp matches 0 run scoreboard players set #r2 tmp 3
+            execute if score #r0 tmp matches 0 run scoreboard players operation #x mcasm *= #r2 tmp
+            scoreboard players set #r0 tmp 0
+            execute unless score #x mcasm = #y mcasm run scoreboard players set #r0 tmp 1
+            execute if score #z mcasm >= #lo mcasm if score #z mcasm <= #hi mcasm run scoreboard players set #r0 tmp 1
+            execute if score #r0 tmp matches 1 run scoreboard players set #r2 tmp 5
+            execute if score #r0 tmp matches 1 run scoreboard players operation #x mcasm *= #r2 tmp
+            scoreboard players set #r0 tmp 7
+            scoreboard players operation #x mcasm *= #r0 tmp
+        "
+        .lines()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n");
+        let mut codegen = Codegen {
+            objective: "tmp".to_string(),
+            ..Default::default()
+        };
+        let mnemonics = parse(source).unwrap();
+        let functions = compile(&mnemonics, &mut codegen).unwrap();
+        assert_eq!(functions[0].serialise(), expected_result);
+
+        let mut setup = Setup::new("tmp");
+        setup.collect(&mnemonics).unwrap();
+        assert_eq!(
+            setup.commands().into_iter().into_single_string(),
+            "scoreboard objectives add mcasm dummy\nscoreboard objectives add tmp dummy"
+        );
+    }
+
+    #[test]
+    fn optimise_test() {
+        let source = "
+            ADD mcasm::#x 0
+            MUL mcasm::#x 1
+            MOV mcasm::#x mcasm::#x
+            MUL mcasm::#x 3
+            MUL mcasm::#y 3
+            MUL mcasm::#x 2
+            MOV mcasm::#t 1
+            MOV mcasm::#t 2
+            ADD mcasm::#y mcasm::#t
+            SUB mcasm::#y mcasm::#t
+            IF mcasm::#x > 0 THEN MUL mcasm::#y 2
+            MOV mcasm::#u 1
+            STN mcasm:s out mcasm::#u 1
+            MOV mcasm::#u 2
+            MOV mcasm::#a 3
+            STORE RESULT mcasm::#a MOV mcasm::#b 5
+            MOV mcasm::#a 3
+            STORE RESULT mcasm::#z MOV mcasm::#a 3
+        ";
+        let expected_result = "
+            scoreboard players add #x mcasm 0
+            scoreboard players set #r0 MC_ASM 3
+            scoreboard players operation #x mcasm *= #r0 MC_ASM
+            scoreboard players operation #y mcasm *= #r0 MC_ASM
+            scoreboard players operation #x mcasm += #x mcasm
+            scoreboard players set #t mcasm 2
+            scoreboard players add #y mcasm 2
+            scoreboard players remove #y mcasm 2
+            execute if score #x mcasm matches 1.. run scoreboard players set #r1 MC_ASM 2
+            execute if score #x mcasm matches 1.. run scoreboard players operation #y mcasm *= #r1 MC_ASM
+            scoreboard players set #u mcasm 1
+            execute store result storage mcasm:s out int 1 run scoreboard players get #u mcasm
+            scoreboard players set #u mcasm 2
+            scoreboard players set #a mcasm 3
+            execute store result score #a mcasm run scoreboard players set #b mcasm 5
+            scoreboard players set #a mcasm 3
+            execute store result score #z mcasm run scoreboard players set #a mcasm 3
+        "
+        .lines()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n");
+        let mut codegen = Codegen {
+            optimise: true,
+            ..Default::default()
+        };
+        let functions = compile(&parse(source).unwrap(), &mut codegen).unwrap();
+        assert_eq!(functions[0].serialise(), expected_result);
+    }
+
+    #[test]
+    fn fold_test() {
+        let source = "
+            MOV mcasm::#B 5
+            MOV mcasm::#C 6
+            MOV mcasm::#A mcasm::#B
+            ADD mcasm::#A mcasm::#C
+            MOV mcasm::#B 5
+            MUL mcasm::#x mcasm::#C
+            JE mcasm::#A 11 ADD mcasm::#y 1
+            JL mcasm::#A mcasm::#B ADD mcasm::#y 2
+            IF mcasm::#x > 0 || mcasm::#B in 1..5 THEN MOV mcasm::#C 0
+            LET mcasm::#D = (mcasm::#A - 1) * mcasm::#B / 3
+            SWP mcasm::#A mcasm::#D
+            SUB mcasm::#D mcasm::#C
+            DIV mcasm::#A 0
+            loop:
+            ADD mcasm::#A mcasm::#B
+        ";
+        let expected_result = "
+            scoreboard players set #B mcasm 5
+            scoreboard players set #C mcasm 6
+            scoreboard players set #A mcasm 5
+            scoreboard players set #A mcasm 11
+            scoreboard players set #r0 MC_ASM 6
+            scoreboard players operation #x mcasm *= #r0 MC_ASM
+            scoreboard players add #y mcasm 1
+            scoreboard players set #C mcasm 0
+            scoreboard players set #D mcasm 16
+            scoreboard players operation #A mcasm >< #D mcasm
+            scoreboard players set #r0 MC_ASM 0
+            scoreboard players operation #A mcasm /= #r0 MC_ASM
+            function mcasm:main/loop
+            scoreboard players operation #A mcasm += #B mcasm
+        "
+        .lines()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n");
+        let mut codegen = Codegen::default();
+        let folded = fold(parse(source).unwrap(), &codegen);
+        let functions = compile(&folded, &mut codegen).unwrap();
+        let stringfied = functions
+            .iter()
+            .map(|function| function.serialise())
+            .collect::<Vec<String>>()
+            .join("\n");
+        assert_eq!(stringfied, expected_result);
+    }
+
+    #[test]
+    fn simulator_test() {
+        let source = "
+            .fixed mcasm::#F 100
+            .long mcasm::#L
+            MOV mcasm::#i 0
+            loop:
+            ADD mcasm::#sum mcasm::#i
+            ADD mcasm::#i 1
+            JL mcasm::#i 10 loop
+            MOV mcasm::#D -7
+            DIV mcasm::#D 2
+            MOV mcasm::#M -7
+            SUR mcasm::#M 2
+            DIV mcasm::#M 0
+            MOV mcasm::#W 2147483647
+            ADD mcasm::#W 1
+            MOV mcasm::#F 1.5
+            MUL mcasm::#F 2.25
+            MOV mcasm::#L 4294967295
+            ADD mcasm::#L 1
+            LET mcasm::#E = (mcasm::#i + 2) * mcasm::#D
+            STN mcasm:data out::<byte> mcasm::#E 1
+            MOV mcasm::#gone 1
+            REL mcasm::#gone
+            JNE mcasm::#gone 1 MOV mcasm::#unset 1
+            JE mcasm::#gone 1 MOV mcasm::#set 1
+        ";
+        let expected_result = "
+            mcasm::#D = -4
+            mcasm::#E = -48
+            mcasm::#F = 337
+            mcasm::#L.hi = 1
+            mcasm::#L.lo = 0
+            mcasm::#M = 1
+            mcasm::#W = -2147483648
+            mcasm::#i = 10
+            mcasm::#sum = 45
+            mcasm::#unset = 1
+            mcasm:data out = -48b
+        "
+        .lines()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n");
+        let mut machine = sim::Machine::new();
+        machine
+            .run(&parse(source).unwrap(), &mut Codegen::default())
+            .unwrap();
+        assert_eq!(machine.dump(), expected_result);
+
+        let mut machine = sim::Machine::new();
+        machine.step_limit = 100;
+        let errors = machine
+            .run(&parse("loop:\nJMP loop").unwrap(), &mut Codegen::default())
+            .unwrap_err();
+        assert!(matches!(
+            errors.as_slice(),
+            [MCAsmError::StepLimitExceeded { limit: 100, .. }]
+        ));
+
+        // `LET` is evaluated as written, leaving the registers as compiling allocates them.
+        let mnemonics = parse(
+            "
+            .fixed mcasm::#f 100
+            MOV mcasm::#f 1.5
+            MOV mcasm::#b 4
+            LET mcasm::#a = mcasm::#b * (mcasm::#f + 1)
+            LET mcasm::#f = mcasm::#b - mcasm::#f
+            ",
+        )
+        .unwrap();
+        let mut compiled = Codegen::default();
+        compile(&mnemonics, &mut compiled).unwrap();
+        let mut codegen = Codegen::default();
+        let mut machine = sim::Machine::new();
+        machine.run(&mnemonics, &mut codegen).unwrap();
+        assert_eq!(codegen.registers, compiled.registers);
+        assert_eq!(
+            machine.dump(),
+            "mcasm::#a = 8\nmcasm::#b = 4\nmcasm::#f = 300"
+        );
+    }
+
+    #[test]
+    fn interpreter_test() {
+        let source = "
+            .fixed mcasm::#F 100
+            .long mcasm::#L
+            MOV mcasm::#i 0
+            loop:
+            ADD mcasm::#sum mcasm::#i
+            ADD mcasm::#i 1
+            JL mcasm::#i 10 loop
+            MOV mcasm::#F 1.5
+            MUL mcasm::#F 2.25
+            MOV mcasm::#G mcasm::#F
+            DIV mcasm::#G mcasm::#F
+            MOV mcasm::#L 4294967295
+            MUL mcasm::#L -3
+            MOV mcasm::#B -100
+            AND mcasm::#B 0x5555
+            XOR mcasm::#B mcasm::#i
+            SHR mcasm::#B 3
+            LET mcasm::#E = (mcasm::#i + 2) * (mcasm::#sum - 50) % 7
+            STN mcasm:data out::<short> mcasm::#F 10
+            NTS mcasm::#N mcasm:data out 3
+            MOV mcasm::#gone 1
+            REL mcasm::#gone
+            IF mcasm::#gone == 1 || mcasm::#i in 0..mcasm::#sum THEN MOV mcasm::#flag 1
+            JL mcasm::#L 0 MOV mcasm::#negative 1
+            .long mcasm::#unset
+            .long mcasm::#read
+            JGE mcasm::#unset mcasm::#i MOV mcasm::#compared 1
+            JNL mcasm::#i mcasm::#unset MOV mcasm::#compared 2
+            ADD mcasm::#low mcasm::#read
+        ";
+        let mnemonics = parse(source).unwrap();
+        let mut codegen = Codegen {
+            constant_pool: true,
+            ..Codegen::default()
+        };
+
+        let mut simulated = sim::Machine::new();
+        simulated.run(&mnemonics, &mut codegen.clone()).unwrap();
+
+        let mut functions = compile(&mnemonics, &mut codegen).unwrap();
+        let mut setup = Setup::default();
+        setup.collect(&mnemonics).unwrap();
+        setup.collect_constants(&codegen);
+        functions.push(setup.to_function("mcasm:load"));
+        let interpreter = sim::Interpreter::new(&functions);
+        let mut interpreted = sim::Machine::new();
+        interpreter.call("mcasm:load", &mut interpreted).unwrap();
+        interpreter.call("mcasm:main", &mut interpreted).unwrap();
+
+        // Registers and constants are the compiler's business.
+        simulated.scores.remove("MC_ASM");
+        interpreted.scores.remove("MC_ASM");
+        assert_eq!(interpreted.dump(), simulated.dump());
+        assert_eq!(
+            interpreted.score(&Scoreboard::new("#F", "mcasm")),
+            Some(337)
+        );
+        assert_eq!(
+            interpreted.score(&Scoreboard::new("#flag", "mcasm")),
+            Some(1)
+        );
+        // Comparing with an unset long register fails and leaves it unset,
+        // while operating with one sets both of its words to 0.
+        assert_eq!(
+            interpreted.score(&Scoreboard::new("#compared", "mcasm")),
+            Some(2)
+        );
+        assert_eq!(
+            interpreted.score(&Scoreboard::new("#unset.lo", "mcasm")),
+            None
+        );
+        assert_eq!(
+            interpreted.score(&Scoreboard::new("#read.hi", "mcasm")),
+            Some(0)
+        );
+    }
+
+    /// xorshift64*, so that a failing program can be told by its seed and generated again.
+    struct Random(u64);
+
+    impl Random {
+        fn new(seed: u64) -> Self {
+            Self(seed ^ 0x9E37_79B9_7F4A_7C15)
+        }
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+        }
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+        fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+            items[self.below(items.len())]
+        }
+        /// Mostly values around 0 and the ends of a score, where overflows and rounding go wrong.
+        fn int(&mut self) -> i64 {
+            match self.below(4) {
+                0 => [0, 1, -1, 2, -2, i32::MAX as i64, i32::MIN as i64, 1 << 30][self.below(8)],
+                1 => self.next() as i32 as i64,
+                _ => self.below(201) as i64 - 100,
+            }
+        }
+    }
+
+    const PLAIN: [&str; 3] = ["mcasm::#a", "mcasm::#b", "mcasm::#c"];
+    /// Declared with a scale of 100.
+    const FIXED: &str = "mcasm::#f";
+    /// Declared as a long register.
+    const LONG: &str = "mcasm::#l";
+
+    /// A literal or a score to operate on the target with, a fraction only for the fixed-point one.
+    fn random_source(random: &mut Random, target: &str) -> String {
+        match random.below(3) {
+            0 => random
+                .pick(&[PLAIN[0], PLAIN[1], PLAIN[2], FIXED])
+                .to_string(),
+            1 if target == FIXED => random.pick(&["0.5", "1.25", "-2.75", "0.01"]).to_string(),
+            _ if target == FIXED => (random.below(2001) as i64 - 1000).to_string(),
+            _ => random.int().to_string(),
+        }
+    }
+
+    fn random_mnemonic(random: &mut Random, labels: &[String]) -> String {
+        let scores = [PLAIN[0], PLAIN[1], PLAIN[2], FIXED];
+        let target = random.pick(&scores);
+        match random.below(11) {
+            0..=2 => {
+                let operation =
+                    random.pick(&["MOV", "ADD", "SUB", "MUL", "DIV", "SUR", "MIN", "MAX"]);
+                let source = random_source(random, target);
+                format!("{} {} {}", operation, target, source)
+            }
+            3 => match random.below(4) {
+                0 => format!("SWP {} {}", target, random.pick(&scores)),
+                _ => format!("{} {}", random.pick(&["NEG", "ABS", "NOT"]), target),
+            },
+            4 => {
+                let target = random.pick(&PLAIN);
+                let operation = random.pick(&["AND", "OR", "XOR", "SHL", "SHR"]);
+                let source = match (random.below(2), operation) {
+                    (0, _) => random.pick(&PLAIN).to_string(),
+                    (_, "SHL" | "SHR") => random.below(41).to_string(),
+                    _ => random.int().to_string(),
+                };
+                format!("{} {} {}", operation, target, source)
+            }
+            5 => {
+                let operation = random.pick(&["MOV", "ADD", "SUB", "MUL"]);
+                match random.below(3) {
+                    0 => format!(
+                        "{} {} {}",
+                        operation,
+                        LONG,
+                        random.next() as i64 >> random.below(64)
+                    ),
+                    1 => format!("{} {} {}", operation, LONG, random.pick(&[PLAIN[0], LONG])),
+                    _ => format!("{} {} {}", operation, random.pick(&PLAIN), LONG),
+                }
+            }
+            6 => format!("REL {}", target),
+            7 => {
+                let path = random.pick(&["p0", "p1", "p2"]);
+                if random.below(2) == 0 {
+                    let storage_type =
+                        random.pick(&["byte", "short", "int", "long", "float", "double"]);
+                    let magnif = random.pick(&["1", "10", "0.5", "0.01"]);
+                    format!(
+                        "STN mcasm:data {}::<{}> {} {}",
+                        path, storage_type, target, magnif
+                    )
+                } else {
+                    format!("NTS {} mcasm:data {} {}", target, path, random.below(3) + 1)
+                }
+            }
+            8 => {
+                let operator = random.pick(&["+", "-", "*", "/", "%"]);
+                let inner = random.pick(&["+", "-", "*", "/", "%"]);
+                format!(
+                    "LET {} = {} {} ({} {} {})",
+                    random.pick(&PLAIN),
+                    random.pick(&PLAIN),
+                    operator,
+                    random.pick(&PLAIN),
+                    inner,
+                    random.int()
+                )
+            }
+            9 => {
+                let (lhs, rhs) = random_clause(random);
+                let mnemonic = match rhs.contains("..") {
+                    true => random.pick(&["JIN", "JNIN"]),
+                    false => random.pick(&[
+                        "JE", "JNE", "JL", "JG", "JLE", "JGE", "JNL", "JNG", "JNLE", "JNGE",
+                    ]),
+                };
+                format!(
+                    "{} {} {} {}",
+                    mnemonic,
+                    lhs,
+                    rhs,
+                    random_guarded(random, labels)
+                )
+            }
+            _ => {
+                let mut clauses = Vec::new();
+                for _ in 0..random.below(3) + 1 {
+                    let (lhs, rhs) = random_clause(random);
+                    let operator = match rhs.contains("..") {
+                        true => random.pick(&["in", "!in"]),
+                        false => random.pick(&["==", "!=", "<", "<=", ">", ">="]),
+                    };
+                    clauses.push(format!("{} {} {}", lhs, operator, rhs));
+                }
+                let mut condition = clauses[0].clone();
+                for clause in &clauses[1..] {
+                    condition = format!("{} {} {}", condition, random.pick(&["&&", "||"]), clause);
+                }
+                format!("IF {} THEN {}", condition, random_guarded(random, labels))
+            }
+        }
+    }
+
+    /// Both sides of a comparison, a range on the right-hand side only for a 32-bit score.
+    fn random_clause(random: &mut Random) -> (String, String) {
+        let lhs = random.pick(&[PLAIN[0], PLAIN[1], PLAIN[2], FIXED, LONG]);
+        let rhs = match random.below(4) {
+            0 => random.pick(&[PLAIN[0], PLAIN[1], LONG]).to_string(),
+            1 if lhs != LONG => match random.below(3) {
+                0 => {
+                    let (a, b) = (random.below(21) as i64 - 10, random.below(21) as i64 - 10);
+                    format!("{}..{}", a.min(b), a.max(b))
+                }
+                1 => format!("..{}", random.int()),
+                _ => format!(
+                    "{}..{}",
+                    random.pick(&["-5", PLAIN[0]]),
+                    random.pick(&PLAIN)
+                ),
+            },
+            _ if lhs == FIXED => (random.below(21) as i64 - 10).to_string(),
+            _ => random.int().to_string(),
+        };
+        (lhs.to_string(), rhs)
+    }
+
+    /// A jump to a later label, so that the program always halts, or an arithmetic mnemonic.
+    /// `LET` and the long ones compile into several commands, which may change what the condition tests.
+    fn random_guarded(random: &mut Random, labels: &[String]) -> String {
+        let target = random.pick(&PLAIN);
+        match random.below(4) {
+            0 if !labels.is_empty() => labels[random.below(labels.len())].clone(),
+            0 | 1 => {
+                let source = random_source(random, target);
+                format!(
+                    "{} {} {}",
+                    random.pick(&["MOV", "ADD", "SUB"]),
+                    target,
+                    source
+                )
+            }
+            2 => format!(
+                "LET {} = {} * {} + {}",
+                target,
+                target,
+                random.pick(&[PLAIN[0], PLAIN[1], "2", "-3"]),
+                random.int()
+            ),
+            _ => format!(
+                "{} {} {}",
+                random.pick(&["MOV", "ADD", "SUB", "MUL"]),
+                LONG,
+                random.pick(&[PLAIN[0], LONG, "3", "-4294967296"])
+            ),
+        }
+    }
+
+    fn random_program(random: &mut Random) -> String {
+        let mut lines = vec![format!(".fixed {} 100", FIXED), format!(".long {}", LONG)];
+        for score in PLAIN {
+            lines.push(format!("MOV {} {}", score, random.int()));
+        }
+        lines.push(format!(
+            "MOV {} {}",
+            FIXED,
+            random.below(2001) as i64 - 1000
+        ));
+        lines.push(format!("MOV {} {}", LONG, random.next() as i64));
+        let blocks = random.below(4) + 1;
+        for block in 0..blocks {
+            if block > 0 {
+                lines.push(format!("l{}:", block));
+            }
+            let later = (block + 1..blocks)
+                .map(|l| format!("l{}", l))
+                .collect::<Vec<String>>();
+            for _ in 0..random.below(10) + 3 {
+                lines.push(random_mnemonic(random, &later));
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// Runs random programs both as mnemonics and as the commands they compile into,
+    /// optimised or not, and expects every score and storage value to end up the same.
+    #[test]
+    fn differential_test() {
+        let mut compared = 0;
+        for seed in 0..400 {
+            let mut random = Random::new(seed);
+            let source = random_program(&mut random);
+            let mnemonics = parse(&source).unwrap_or_else(|e| panic!("seed {}: {:?}", seed, e));
+            let mut codegen = Codegen {
+                constant_pool: random.below(2) == 0,
+                optimise: random.below(2) == 0,
+                ..Codegen::default()
+            };
+            // Programs the compiler rejects, e.g. for an inexact fraction, have nothing to compare.
+            let mut simulated = sim::Machine::new();
+            if simulated.run(&mnemonics, &mut codegen.clone()).is_err() {
+                continue;
+            }
+            let mnemonics = if codegen.optimise {
+                fold(mnemonics, &codegen)
+            } else {
+                mnemonics
+            };
+            let mut functions = compile(&mnemonics, &mut codegen).unwrap();
+            let mut setup = Setup::default();
+            setup.collect(&mnemonics).unwrap();
+            setup.collect_constants(&codegen);
+            functions.push(setup.to_function("mcasm:load"));
+            let interpreter = sim::Interpreter::new(&functions);
+            let mut interpreted = sim::Machine::new();
+            interpreter
+                .call("mcasm:load", &mut interpreted)
+                .and_then(|_| interpreter.call("mcasm:main", &mut interpreted))
+                .unwrap_or_else(|e| panic!("seed {}: {:?}\n{}", seed, e, source));
+
+            simulated.scores.remove("MC_ASM");
+            interpreted.scores.remove("MC_ASM");
+            assert_eq!(
+                interpreted.dump(),
+                simulated.dump(),
+                "seed {}, {:?}:\n{}",
+                seed,
+                codegen,
+                source
+            );
+            compared += 1;
+        }
+        assert!(compared >= 300, "only {} programs compiled", compared);
+    }
+
+    #[test]
+    fn ast_visit_test() {
+        use visit::{Fold, Visitor};
+
+        let source = "\
+.objective money dummy \"Money\"
+.fixed mcasm::#f 100
+MOV mcasm::#f 1.5
+LET mcasm::#a = (mcasm::#b + 2) * - (mcasm::#c - mcasm::#a - (1 - 2))
+start:
+JNIN mcasm::#a mcasm::#b..5 start
+IF mcasm::#a != 0 && mcasm::#b in 1..10 || mcasm::#c >= mcasm::#a THEN ADD mcasm::#b -3
+STORE RESULT mcasm::#c UNLESS DATA STORAGE mcasm:data flag NTS mcasm::#b mcasm:data value::<long> 10
+REL mcasm::#c";
+        let mnemonics = parse(source).unwrap();
+        let printed = mnemonics
+            .iter()
+            .map(Mnemonic::to_string)
+            .collect::<Vec<String>>();
+        assert_eq!(printed.join("\n"), source);
+        for (mnemonic, line) in mnemonics.iter().zip(&printed) {
+            assert_eq!(
+                parse_line(0, line).unwrap().to_string(),
+                mnemonic.to_string()
+            );
+        }
+        assert_eq!(mnemonics.clone(), mnemonics);
+
+        struct Scores<'ast>(Vec<&'ast Scoreboard>);
+        impl<'ast> Visitor<'ast> for Scores<'ast> {
+            fn visit_scoreboard(&mut self, scoreboard: &'ast Scoreboard) {
+                self.0.push(scoreboard);
+            }
+        }
+        let mut scores = Scores(Vec::new());
+        scores.visit_program(&mnemonics);
+        assert_eq!(scores.0.len(), 16);
+        assert_eq!(scores.0[1].span, Span::new(2, 4, 13));
+
+        struct Rename;
+        impl Fold for Rename {
+            fn fold_scoreboard(&mut self, scoreboard: Scoreboard) -> Scoreboard {
+                Scoreboard {
+                    objective: "game".to_string(),
+                    ..scoreboard
+                }
+            }
+        }
+        let renamed = Rename.fold_program(mnemonics);
+        assert_eq!(renamed[5].to_string(), "JNIN game::#a game::#b..5 start");
+        assert!(
+            renamed
+                .iter()
+                .map(Mnemonic::to_string)
+                .all(|line| !line.contains("mcasm::"))
+        );
+    }
+
+    #[test]
+    fn disassemble_test() {
+        let source = "
+            MOV mcasm::#a 7
+            MOV mcasm::#b mcasm::#a
+            ADD mcasm::#a 5
+            SUB mcasm::#a -2
+            MUL mcasm::#b 3
+            SUR mcasm::#b mcasm::#a
+            MAX mcasm::#b mcasm::#a
+            SWP mcasm::#a mcasm::#b
+            STN mcasm:data value::<short> mcasm::#a 2
+            NTS mcasm::#c mcasm:data value 3
+            JL mcasm::#a 20 MOV mcasm::#d 1
+            JNIN mcasm::#c 0..9 SUB mcasm::#c 1
+            MOV mcasm::#gone 1
+            REL mcasm::#gone
+        ";
+        let mnemonics = parse(source).unwrap();
+        let mut codegen = Codegen::default();
+        let main = compile(&mnemonics, &mut codegen).unwrap().remove(0);
+        let mcfunction = format!("# {}\n{}\nsay done", main.name, main.serialise());
+
+        let lines = disasm::disassemble(&mcfunction, &codegen.entry());
+        let disassembled = lines
+            .iter()
+            .map(disasm::Line::to_string)
+            .collect::<Vec<String>>()
+            .join("\n");
+        assert_eq!(
+            disassembled,
+            "\
+// mcasm:main
+MOV mcasm::#a 7
+MOV mcasm::#b mcasm::#a
+ADD mcasm::#a 5
+ADD mcasm::#a 2
+MOV MC_ASM::#r0 3
+MUL mcasm::#b MC_ASM::#r0
+SUR mcasm::#b mcasm::#a
+MAX mcasm::#b mcasm::#a
+SWP mcasm::#a mcasm::#b
+STN mcasm:data value::<short> mcasm::#a 2
+NTS mcasm::#c mcasm:data value::<int> 3
+JIN mcasm::#a ..19 MOV mcasm::#d 1
+JNIN mcasm::#c 0..9 SUB mcasm::#c 1
+MOV mcasm::#gone 1
+REL mcasm::#gone
+say done"
+        );
+        assert_eq!(
+            lines.last(),
+            Some(&disasm::Line::Raw("say done".to_string()))
+        );
+
+        // Running the disassembly does what the program does.
+        let mut original = sim::Machine::new();
+        original.run(&mnemonics, &mut Codegen::default()).unwrap();
+        let reassembled = parse(&disassembled.replace("say done", "")).unwrap();
+        let mut machine = sim::Machine::new();
+        machine.run(&reassembled, &mut Codegen::default()).unwrap();
+        machine.scores.remove("MC_ASM");
+        assert_eq!(machine.dump(), original.dump());
+
+        // Only a jump into a block of the function disassembled is a label.
+        let jumps = "\
+return run function mcasm:main/loop
+return run function mcasm:other/loop
+return run function other:main/loop
+return run function mcasm:main
+execute if score #a mcasm matches 1 run return run function other:main/loop";
+        let lines = disasm::disassemble(jumps, "mcasm:main");
+        assert_eq!(
+            lines
+                .iter()
+                .map(disasm::Line::to_string)
+                .collect::<Vec<String>>(),
+            ["JMP loop"]
+                .into_iter()
+                .chain(jumps.lines().skip(1))
+                .collect::<Vec<&str>>()
+        );
+    }
 }
@@ -1,27 +1,110 @@
 use colored::Colorize;
-use mc_assembly::{IntoSingleString, MCAsmError, Mnemonic};
+const USAGE: &str = "usage: mcasm [<source>] [--constant-pool] [--allow-wrapping] [--register-objective <objective>] [-O]\n       mcasm build <source> -o <output> [options]\n       mcasm run <source> [--dump] [options]\n       mcasm disasm <mcfunction> [-o <output>] [--function <function>]";
+
+#[path = "mcasm/build.rs"]
+mod build;
+#[path = "mcasm/disasm.rs"]
+mod disasm;
+#[path = "mcasm/run.rs"]
+mod run;
+
+use mc_assembly::{Codegen, Function, MCAsmError, Setup};
 use std::env;
 use std::error::Error;
 use std::io::Read;
-use std::path::PathBuf;
-
-fn show_compiled_mcfunction(assembly: &str) -> Result<String, String> {
-    let result = mc_assembly::parse(&assembly);
-    let show_ok_as_string = |arg: Vec<Mnemonic>| {
-        arg.iter()
-            .filter_map(|mnemonic| mnemonic.to_qualified().ok())
-            .flatten()
-            .into_single_string()
-    };
-    let show_err_as_string = |e: Vec<(usize, MCAsmError)>| {
+use std::path::{Path, PathBuf};
+
+/// Renders an error the way rustc does, with the offending line and carets under the token.
+fn render_error(error: &MCAsmError, assembly: &str, path: &Path) -> String {
+    let span = error.span();
+    let line = assembly.lines().nth(span.line).unwrap_or("");
+    let column = line.get(..span.start).unwrap_or(line).chars().count();
+    let width = line
+        .get(span.start..span.end)
+        .map(|s| s.chars().count())
+        .unwrap_or(0)
+        .max(1);
+    let line_number = (span.line + 1).to_string();
+    let gutter = " ".repeat(line_number.len());
+    format!(
+        "{}: {}\n{}{} {}:{}:{}\n{} {}\n{} {} {}\n{} {} {}{}",
+        "error".red().bold(),
+        error.to_string().bold(),
+        gutter,
+        "-->".cyan().bold(),
+        path.display(),
+        span.line + 1,
+        column + 1,
+        gutter,
+        "|".cyan().bold(),
+        line_number.cyan().bold(),
+        "|".cyan().bold(),
+        line,
+        gutter,
+        "|".cyan().bold(),
+        " ".repeat(column),
+        "^".repeat(width).red().bold(),
+    )
+}
+
+/// Parses and compiles a source, rendering every error found on the way.
+fn compile_source(
+    assembly: &str,
+    path: &Path,
+    codegen: &mut Codegen,
+    setup: &mut Setup,
+) -> Result<Vec<Function>, String> {
+    let show_err_as_string = |e: Vec<MCAsmError>| {
         e.iter()
-            .map(|(index, error)| format!("An error occured at line {}: {:?}", index, error))
+            .map(|error| render_error(error, assembly, path))
             .collect::<Vec<String>>()
-            .join("\n")
+            .join("\n\n")
+    };
+    let mnemonics = mc_assembly::parse(assembly).map_err(show_err_as_string)?;
+    setup.collect(&mnemonics).map_err(show_err_as_string)?;
+    let mnemonics = if codegen.optimise {
+        // Errors are reported for the program as written, before folding drops anything.
+        mc_assembly::compile(&mnemonics, &mut codegen.clone()).map_err(show_err_as_string)?;
+        mc_assembly::fold(mnemonics, codegen)
+    } else {
+        mnemonics
     };
-    result
-        .map(|o| show_ok_as_string(o))
-        .map_err(|e| show_err_as_string(e))
+    let functions = mc_assembly::compile(&mnemonics, codegen).map_err(show_err_as_string)?;
+    setup.collect_constants(codegen);
+    Ok(functions)
+}
+
+fn show_compiled_mcfunction(
+    assembly: &str,
+    path: &Path,
+    codegen: &mut Codegen,
+) -> Result<String, String> {
+    let mut setup = Setup::new(&codegen.objective);
+    let functions = compile_source(assembly, path, codegen, &mut setup)?;
+    Ok(
+        std::iter::once(setup.to_function(format!("{}:load", codegen.namespace)))
+            .chain(functions)
+            .map(|function| format!("# {}\n{}", function.name, function.serialise()))
+            .collect::<Vec<String>>()
+            .join("\n\n"),
+    )
+}
+
+/// Turns a file name into something usable as a function path, e.g. `My Loop.mcasm` to `my_loop`.
+fn function_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| sanitise(&stem.to_string_lossy()))
+        .unwrap_or_else(|| "main".to_string())
+}
+
+fn sanitise(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | '0'..='9' | '_' | '-' | '.' => c,
+            _ => '_',
+        })
+        .collect()
 }
 
 #[derive(Debug)]
@@ -41,11 +124,9 @@ fn edit_using_tui_text_editor() -> Result<(String, PathBuf), Box<dyn Error>> {
         Ok(o) => o,
         Err(_) => {
             println!(
-                "{}: {}\n {} {}\n",
+                "{}: both enviroment variables of VISUAL and EDITOR not set.\n {} Vim will be used as editor automatically.\n",
                 "warning".yellow(),
-                "both enviroment variables of VISUAL and EDITOR not set.",
                 "-->".cyan(),
-                "Vim will be used as editor automatically."
             );
             "vim".to_string()
         }
@@ -70,9 +151,57 @@ fn edit_using_tui_text_editor() -> Result<(String, PathBuf), Box<dyn Error>> {
 
 fn main() {
     let args = env::args().collect::<Vec<String>>();
-    let (assembly, path) = if let Some(path) = args.get(1) {
+    if args.get(1).is_some_and(|arg| arg == "build") {
+        if let Err(e) = build::BuildOptions::parse(&args[2..]).and_then(|o| build::build(&o)) {
+            println!("{}", e);
+        }
+        return;
+    }
+    if args.get(1).is_some_and(|arg| arg == "run") {
+        if let Err(e) = run::RunOptions::parse(&args[2..]).and_then(|o| run::run(&o)) {
+            println!("{}", e);
+        }
+        return;
+    }
+    if args.get(1).is_some_and(|arg| arg == "disasm") {
+        if let Err(e) = disasm::DisasmOptions::parse(&args[2..]).and_then(|o| disasm::disasm(&o)) {
+            println!("{}", e);
+        }
+        return;
+    }
+    let mut paths = Vec::new();
+    let mut constant_pool = false;
+    let mut allow_wrapping = false;
+    let mut register_objective = None;
+    let mut optimise = false;
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--constant-pool" => constant_pool = true,
+            "--allow-wrapping" => allow_wrapping = true,
+            "-O" => optimise = true,
+            "--register-objective" => match args.next() {
+                Some(objective) => register_objective = Some(objective.clone()),
+                None => {
+                    println!("{}: `{}` needs a value\n{}", "error".red(), arg, USAGE);
+                    return;
+                }
+            },
+            _ if arg.starts_with('-') => {
+                println!("{}: unknown option `{}`\n{}", "error".red(), arg, USAGE);
+                return;
+            }
+            _ => paths.push(arg),
+        }
+    }
+
+    let (assembly, path, name) = if let Some(path) = paths.first() {
         match std::fs::read_to_string(path) {
-            Ok(assembly) => (assembly, PathBuf::from(path)),
+            Ok(assembly) => {
+                let path = PathBuf::from(path);
+                let name = function_name(&path);
+                (assembly, path, name)
+            }
             Err(e) => {
                 println!(
                     "{}: failed to load the specified file.\nDetail: {}",
@@ -84,7 +213,7 @@ fn main() {
         }
     } else {
         match edit_using_tui_text_editor() {
-            Ok((assembly, path)) => (assembly, path),
+            Ok((assembly, path)) => (assembly, path, "main".to_string()),
             Err(e) => {
                 println!(
                     "{}: failed to get edited string.\n\nCaused by:\n\t{}",
@@ -98,14 +227,21 @@ fn main() {
 
     println!("{:>12} {}", "Compiling".green().bold(), path.display());
 
-    match show_compiled_mcfunction(&assembly) {
+    let mut codegen = Codegen::new("mcasm", name);
+    codegen.constant_pool = constant_pool;
+    codegen.allow_wrapping = allow_wrapping;
+    codegen.optimise = optimise;
+    if let Some(objective) = register_objective {
+        codegen.objective = objective;
+    }
+    match show_compiled_mcfunction(&assembly, &path, &mut codegen) {
         Ok(s) => {
             println!("{:>12} {}", "Finishing".green().bold(), path.display());
             println!("\n------ Compiled MCFunction ------\n\n{}", s);
         }
         Err(e) => {
             println!("{}", "Compilation failed\n".red().bold());
-            println!("------ Errors------\n{}", e);
+            println!("{}", e);
         }
     }
     println!();
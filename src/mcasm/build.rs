@@ -0,0 +1,196 @@
+use colored::Colorize;
+use mc_assembly::{Codegen, Datapack, Setup};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::{compile_source, function_name, sanitise};
+
+const USAGE: &str = "usage: mcasm build <source> -o <output> [--namespace <namespace>] [--pack-format <number>] [--description <text>] [--load <function>] [--tick <function>] [--constant-pool] [--allow-wrapping] [--register-objective <objective>] [-O]";
+
+pub struct BuildOptions {
+    /// A `.mcasm` file or a directory searched for them recursively.
+    pub source: PathBuf,
+    /// A directory, or a zip archive when it ends with `.zip`.
+    pub output: PathBuf,
+    pub namespace: String,
+    pub pack_format: u32,
+    pub description: String,
+    pub load: Vec<String>,
+    pub tick: Vec<String>,
+    pub constant_pool: bool,
+    pub allow_wrapping: bool,
+    /// Objective of the registers and constants.
+    pub register_objective: String,
+    pub optimise: bool,
+}
+
+impl BuildOptions {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let error = |message: String| format!("{}: {}\n{}", "error".red(), message, USAGE);
+        let mut source = None;
+        let mut output = None;
+        let mut namespace = "mcasm".to_string();
+        let mut pack_format = Datapack::DEFAULT_PACK_FORMAT;
+        let mut description = "Compiled by MCAssembly".to_string();
+        let mut load = Vec::new();
+        let mut tick = Vec::new();
+        let mut constant_pool = false;
+        let mut allow_wrapping = false;
+        let mut register_objective = "MC_ASM".to_string();
+        let mut optimise = false;
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| error(format!("`{}` needs a value", arg)))
+            };
+            match arg.as_str() {
+                "-o" | "--output" => output = Some(PathBuf::from(value()?)),
+                "--namespace" => namespace = value()?,
+                "--pack-format" => {
+                    let number = value()?;
+                    pack_format = number
+                        .parse()
+                        .map_err(|_| error(format!("`{}` is not a pack format", number)))?;
+                }
+                "--description" => description = value()?,
+                "--load" => load.push(value()?),
+                "--tick" => tick.push(value()?),
+                "--constant-pool" => constant_pool = true,
+                "--allow-wrapping" => allow_wrapping = true,
+                "--register-objective" => register_objective = value()?,
+                "-O" => optimise = true,
+                _ if arg.starts_with('-') => Err(error(format!("unknown option `{}`", arg)))?,
+                _ if source.is_none() => source = Some(PathBuf::from(arg)),
+                _ => Err(error(format!("unexpected argument `{}`", arg)))?,
+            }
+        }
+
+        let qualify = |function: String| {
+            if function.contains(':') {
+                function
+            } else {
+                format!("{}:{}", namespace, function)
+            }
+        };
+        Ok(Self {
+            source: source.ok_or_else(|| error("no source given".to_string()))?,
+            output: output.ok_or_else(|| error("no output given".to_string()))?,
+            load: load.into_iter().map(qualify).collect(),
+            tick: tick.into_iter().map(qualify).collect(),
+            namespace,
+            pack_format,
+            description,
+            constant_pool,
+            allow_wrapping,
+            register_objective,
+            optimise,
+        })
+    }
+}
+
+/// Every `.mcasm` file under the directory, paired with the function path it compiles into.
+fn collect_sources(root: &Path, directory: &Path) -> std::io::Result<Vec<(PathBuf, String)>> {
+    let mut sources = Vec::new();
+    let mut entries = std::fs::read_dir(directory)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<PathBuf>>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            sources.extend(collect_sources(root, &path)?);
+        } else if path.extension().is_some_and(|e| e == "mcasm") {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            let mut components = relative
+                .parent()
+                .into_iter()
+                .flat_map(|parent| parent.iter())
+                .map(|component| sanitise(&component.to_string_lossy()))
+                .collect::<Vec<String>>();
+            components.push(function_name(&path));
+            sources.push((path, components.join("/")));
+        }
+    }
+    Ok(sources)
+}
+
+pub fn build(options: &BuildOptions) -> Result<(), String> {
+    let io_error = |path: &Path, e: std::io::Error| {
+        format!(
+            "{}: failed to access {}.\nDetail: {}",
+            "error".red(),
+            path.display(),
+            e
+        )
+    };
+    let sources = if options.source.is_dir() {
+        collect_sources(&options.source, &options.source)
+            .map_err(|e| io_error(&options.source, e))?
+    } else {
+        vec![(options.source.clone(), function_name(&options.source))]
+    };
+
+    let mut datapack = Datapack::new(options.pack_format, options.description.clone());
+    datapack.load = options.load.clone();
+    datapack.tick = options.tick.clone();
+
+    let mut setup = Setup::new(&options.register_objective);
+    let mut errors = Vec::new();
+    // Where each function came from, as a file, a label and a helper can all compile into the same path,
+    // e.g. `a/b.mcasm` and the label `b` of `a.mcasm`.
+    let mut compiled: HashMap<String, PathBuf> = HashMap::new();
+    for (path, name) in sources {
+        println!("{:>12} {}", "Compiling".green().bold(), path.display());
+        let assembly = std::fs::read_to_string(&path).map_err(|e| io_error(&path, e))?;
+        let mut codegen = Codegen::new(&options.namespace, name);
+        codegen.constant_pool = options.constant_pool;
+        codegen.allow_wrapping = options.allow_wrapping;
+        codegen.objective = options.register_objective.clone();
+        codegen.optimise = options.optimise;
+        match compile_source(&assembly, &path, &mut codegen, &mut setup) {
+            Ok(functions) => {
+                for function in &functions {
+                    if let Some(previous) = compiled.insert(function.name.clone(), path.clone()) {
+                        let sources = if previous == path {
+                            format!("twice from {}", path.display())
+                        } else {
+                            format!("from both {} and {}", previous.display(), path.display())
+                        };
+                        errors.push(format!(
+                            "{}: `{}` is compiled {}",
+                            "error".red(),
+                            function.name,
+                            sources
+                        ));
+                    }
+                }
+                datapack.functions.extend(functions);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(format!(
+            "{}\n{}",
+            "Compilation failed\n".red().bold(),
+            errors.join("\n\n")
+        ));
+    }
+
+    datapack.setup(setup.to_function(format!("{}:load", options.namespace)));
+
+    if options.output.extension().is_some_and(|e| e == "zip") {
+        datapack.write_to_zip(&options.output)
+    } else {
+        datapack.write_to_directory(&options.output)
+    }
+    .map_err(|e| io_error(&options.output, e))?;
+    println!(
+        "{:>12} {}",
+        "Finishing".green().bold(),
+        options.output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use colored::Colorize;
+use mc_assembly::disasm::{Line, disassemble};
+use std::path::PathBuf;
+
+const USAGE: &str = "usage: mcasm disasm <mcfunction> [-o <output>] [--function <function>]";
+
+pub struct DisasmOptions {
+    pub source: PathBuf,
+    /// Where the source is written, or `None` to print it alone, e.g. to be redirected.
+    pub output: Option<PathBuf>,
+    /// The function the source was compiled as, whose blocks `return run function` jumps to.
+    pub function: String,
+}
+
+impl DisasmOptions {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let error = |message: String| format!("{}: {}\n{}", "error".red(), message, USAGE);
+        let mut source = None;
+        let mut output = None;
+        let mut function = "mcasm:main".to_string();
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-o" | "--output" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| error(format!("`{}` needs a value", arg)))?;
+                    output = Some(PathBuf::from(path));
+                }
+                "--function" => {
+                    function = args
+                        .next()
+                        .ok_or_else(|| error(format!("`{}` needs a value", arg)))?
+                        .clone();
+                }
+                _ if arg.starts_with('-') => Err(error(format!("unknown option `{}`", arg)))?,
+                _ if source.is_none() => source = Some(PathBuf::from(arg)),
+                _ => Err(error(format!("unexpected argument `{}`", arg)))?,
+            }
+        }
+
+        Ok(Self {
+            source: source.ok_or_else(|| error("no source given".to_string()))?,
+            output,
+            function,
+        })
+    }
+}
+
+pub fn disasm(options: &DisasmOptions) -> Result<(), String> {
+    let path = &options.source;
+    let mcfunction = std::fs::read_to_string(path).map_err(|e| {
+        format!(
+            "{}: failed to access {}.\nDetail: {}",
+            "error".red(),
+            path.display(),
+            e
+        )
+    })?;
+    let lines = disassemble(&mcfunction, &options.function);
+    let assembly = lines
+        .iter()
+        .map(Line::to_string)
+        .collect::<Vec<String>>()
+        .join("\n");
+    let Some(output) = &options.output else {
+        println!("{}", assembly);
+        return Ok(());
+    };
+
+    println!("{:>12} {}", "Disassembling".green().bold(), path.display());
+    std::fs::write(output, assembly + "\n").map_err(|e| {
+        format!(
+            "{}: failed to write {}.\nDetail: {}",
+            "error".red(),
+            output.display(),
+            e
+        )
+    })?;
+    for (index, line) in lines.iter().enumerate() {
+        if let Line::Raw(command) = line {
+            println!(
+                "{}: line {} has no mnemonic and is kept as it is: `{}`",
+                "warning".yellow(),
+                index + 1,
+                command
+            );
+        }
+    }
+    println!("{:>12} {}", "Finishing".green().bold(), output.display());
+    Ok(())
+}
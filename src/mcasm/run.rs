@@ -0,0 +1,97 @@
+use colored::Colorize;
+use mc_assembly::{Codegen, MCAsmError, sim::Machine};
+use std::path::PathBuf;
+
+use crate::{function_name, render_error};
+
+const USAGE: &str = "usage: mcasm run <source> [--dump] [--step-limit <number>] [--allow-wrapping] [--register-objective <objective>]";
+
+pub struct RunOptions {
+    pub source: PathBuf,
+    /// Whether every score and storage value is printed once the program halts.
+    pub dump: bool,
+    pub step_limit: usize,
+    pub allow_wrapping: bool,
+    /// Objective of the registers, which `LET` writes its temporaries to.
+    pub register_objective: String,
+}
+
+impl RunOptions {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let error = |message: String| format!("{}: {}\n{}", "error".red(), message, USAGE);
+        let mut source = None;
+        let mut dump = false;
+        let mut step_limit = Machine::DEFAULT_STEP_LIMIT;
+        let mut allow_wrapping = false;
+        let mut register_objective = "MC_ASM".to_string();
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| error(format!("`{}` needs a value", arg)))
+            };
+            match arg.as_str() {
+                "--dump" => dump = true,
+                "--step-limit" => {
+                    let number = value()?;
+                    step_limit = number
+                        .parse()
+                        .map_err(|_| error(format!("`{}` is not a step limit", number)))?;
+                }
+                "--allow-wrapping" => allow_wrapping = true,
+                "--register-objective" => register_objective = value()?,
+                _ if arg.starts_with('-') => Err(error(format!("unknown option `{}`", arg)))?,
+                _ if source.is_none() => source = Some(PathBuf::from(arg)),
+                _ => Err(error(format!("unexpected argument `{}`", arg)))?,
+            }
+        }
+
+        Ok(Self {
+            source: source.ok_or_else(|| error("no source given".to_string()))?,
+            dump,
+            step_limit,
+            allow_wrapping,
+            register_objective,
+        })
+    }
+}
+
+pub fn run(options: &RunOptions) -> Result<(), String> {
+    let path = &options.source;
+    let assembly = std::fs::read_to_string(path).map_err(|e| {
+        format!(
+            "{}: failed to access {}.\nDetail: {}",
+            "error".red(),
+            path.display(),
+            e
+        )
+    })?;
+    let show_err_as_string = |e: Vec<MCAsmError>| {
+        format!(
+            "{}\n{}",
+            "Run failed\n".red().bold(),
+            e.iter()
+                .map(|error| render_error(error, &assembly, path))
+                .collect::<Vec<String>>()
+                .join("\n\n")
+        )
+    };
+
+    println!("{:>12} {}", "Running".green().bold(), path.display());
+    let mut codegen = Codegen::new("mcasm", function_name(path));
+    codegen.allow_wrapping = options.allow_wrapping;
+    codegen.objective = options.register_objective.clone();
+    let mnemonics = mc_assembly::parse(&assembly).map_err(show_err_as_string)?;
+    let mut machine = Machine::new();
+    machine.step_limit = options.step_limit;
+    machine
+        .run(&mnemonics, &mut codegen)
+        .map_err(show_err_as_string)?;
+    println!("{:>12} {}", "Finishing".green().bold(), path.display());
+    if options.dump {
+        println!("\n{}", machine.dump());
+    }
+    Ok(())
+}
@@ -0,0 +1,685 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::types::*;
+
+/// A command as far as the optimiser understands it. Selectors and `*` are never understood,
+/// as they stand for a number of scores.
+enum Effect {
+    Set(Scoreboard, i32),
+    /// `add`, or `remove` with the amount negated.
+    Add(Scoreboard, i32),
+    Operation(Scoreboard, String, Scoreboard),
+    Reset(Scoreboard),
+    /// Anything else, which may read or write any score.
+    Opaque,
+}
+
+impl Effect {
+    fn parse(command: &str) -> Self {
+        let score = |holder: &str, objective: &str| {
+            (!holder.starts_with('@') && holder != "*").then(|| Scoreboard::new(holder, objective))
+        };
+        let tokens = command.split_whitespace().collect::<Vec<&str>>();
+        let effect = match tokens.as_slice() {
+            ["scoreboard", "players", "set", holder, objective, value] => score(holder, objective)
+                .zip(value.parse().ok())
+                .map(|(score, value)| Self::Set(score, value)),
+            ["scoreboard", "players", "add", holder, objective, value] => score(holder, objective)
+                .zip(value.parse().ok())
+                .map(|(score, value)| Self::Add(score, value)),
+            ["scoreboard", "players", "remove", holder, objective, value] => {
+                score(holder, objective)
+                    .zip(value.parse::<i32>().ok())
+                    .map(|(score, value)| Self::Add(score, value.wrapping_neg()))
+            }
+            [
+                "scoreboard",
+                "players",
+                "operation",
+                holder,
+                objective,
+                operation,
+                source,
+                from,
+            ] => score(holder, objective)
+                .zip(score(source, from))
+                .map(|(target, source)| Self::Operation(target, operation.to_string(), source)),
+            ["scoreboard", "players", "reset", holder, objective] => {
+                score(holder, objective).map(Self::Reset)
+            }
+            _ => None,
+        };
+        effect.unwrap_or(Self::Opaque)
+    }
+    /// What a qualified command does. A `store` modifier writes its score whatever the command does,
+    /// so a command carrying one is opaque, which keeps it as it is.
+    fn of(qualified: &Qualified) -> Self {
+        let stores = qualified
+            .conditions()
+            .iter()
+            .any(|condition| condition.starts_with("store "));
+        if stores {
+            Self::Opaque
+        } else {
+            Self::parse(qualified.command())
+        }
+    }
+}
+
+/// Every score a text might mention, taking each pair of adjacent words as a holder and an objective.
+/// It finds more than there are, which is safe for telling what a command may read.
+fn mentioned(text: &str) -> Vec<String> {
+    let words = text.split_whitespace().collect::<Vec<&str>>();
+    words
+        .windows(2)
+        .map(|pair| Codegen::key(&Scoreboard::new(pair[0], pair[1])))
+        .collect()
+}
+
+/// Rewrites the commands of a function into fewer or cheaper ones doing the same.
+///
+/// - `add x 0`, `remove x 0`, `operation x = x`, and `*=` or `/=` by a score holding 1 are removed,
+///   once `x` is known to be set, as they'd set it to 0 otherwise.
+/// - `set` of the value the score already holds is removed, e.g. a register set to the same literal again.
+/// - `+=` and `-=` of a score holding a known value become `add` or `remove`.
+/// - `*=` of a score holding 2 becomes `+=` of itself.
+/// - A store to a fake player which is overwritten before anything reads it is removed.
+///
+/// Values are only known from unconditional commands earlier in the function and from the constant pool.
+/// A command carrying a `store` modifier is kept as it is, and forgets every value known.
+/// Registers are dead at the end of the function, while any other score may be read later.
+pub fn optimise(body: Vec<Qualified>, codegen: &Codegen) -> Vec<Qualified> {
+    eliminate_dead_stores(rewrite(body, codegen), codegen)
+}
+
+/// The value of a constant in the pool, e.g. 3 for `#c3 MC_ASM`, which is set once on load.
+fn constant(score: &Scoreboard, codegen: &Codegen) -> Option<i32> {
+    if score.objective != codegen.objective {
+        return None;
+    }
+    score.scoreholder.strip_prefix("#c")?.parse().ok()
+}
+
+fn is_register(key: &str, codegen: &Codegen) -> bool {
+    key.strip_prefix(&format!("{}::#r", codegen.objective))
+        .is_some_and(|index| !index.is_empty() && index.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Rewrites each command by the values of the scores known at that point.
+fn rewrite(body: Vec<Qualified>, codegen: &Codegen) -> Vec<Qualified> {
+    let mut known: HashMap<String, i32> = HashMap::new();
+    // Scores certainly set, which a command leaving them as they are can't create as 0.
+    let mut existing: HashSet<String> = HashSet::new();
+    let mut res = Vec::new();
+    for qualified in body {
+        let value = |known: &HashMap<String, i32>, score: &Scoreboard| {
+            known
+                .get(&Codegen::key(score))
+                .copied()
+                .or_else(|| constant(score, codegen))
+        };
+        let exists = |score: &Scoreboard| {
+            score.objective == codegen.objective || existing.contains(&Codegen::key(score))
+        };
+        let unconditional = qualified.conditions().is_empty();
+        let rewritten = match Effect::of(&qualified) {
+            Effect::Add(score, 0) if exists(&score) => None,
+            Effect::Set(score, set) if value(&known, &score) == Some(set) => None,
+            Effect::Operation(target, operation, source) => {
+                let same = Codegen::key(&target) == Codegen::key(&source);
+                match (operation.as_str(), value(&known, &source)) {
+                    ("=", _) if same && exists(&target) => None,
+                    ("*=" | "/=", Some(1)) if exists(&target) => None,
+                    ("*=", Some(2)) => Some(qualified.replaced(target.operate("+=", &target))),
+                    ("+=", Some(added)) if !same => match added_by(&target, added) {
+                        Some(command) => Some(qualified.replaced(command)),
+                        None => Some(qualified),
+                    },
+                    ("-=", Some(subtracted)) if !same => {
+                        match added_by(&target, subtracted.wrapping_neg()) {
+                            Some(command) => Some(qualified.replaced(command)),
+                            None => Some(qualified),
+                        }
+                    }
+                    _ => Some(qualified),
+                }
+            }
+            _ => Some(qualified),
+        };
+        let Some(qualified) = rewritten else {
+            continue;
+        };
+        match Effect::of(&qualified) {
+            Effect::Set(score, _) | Effect::Add(score, _) if unconditional => {
+                existing.insert(Codegen::key(&score));
+            }
+            Effect::Operation(target, _, source) if unconditional => {
+                existing.extend([&target, &source].map(Codegen::key));
+            }
+            Effect::Reset(score) => {
+                existing.remove(&Codegen::key(&score));
+            }
+            Effect::Opaque => existing.clear(),
+            _ => {}
+        }
+        match Effect::of(&qualified) {
+            Effect::Set(score, set) if unconditional => {
+                known.insert(Codegen::key(&score), set);
+            }
+            Effect::Add(score, added) if unconditional => {
+                if let Some(held) = known.get_mut(&Codegen::key(&score)) {
+                    *held = held.wrapping_add(added);
+                }
+            }
+            Effect::Operation(target, operation, source) => {
+                let copied = value(&known, &source).filter(|_| unconditional && operation == "=");
+                match copied {
+                    Some(copied) => known.insert(Codegen::key(&target), copied),
+                    None => known.remove(&Codegen::key(&target)),
+                };
+                if operation == "><" {
+                    known.remove(&Codegen::key(&source));
+                }
+            }
+            Effect::Set(score, _) | Effect::Add(score, _) | Effect::Reset(score) => {
+                known.remove(&Codegen::key(&score));
+            }
+            Effect::Opaque => known.clear(),
+        }
+        res.push(qualified);
+    }
+    res
+}
+
+/// `add` or `remove` of the amount, or `None` when the amount is `i32::MIN`, which neither of them takes.
+fn added_by(target: &Scoreboard, amount: i32) -> Option<Command> {
+    match amount.checked_neg() {
+        None => None,
+        Some(opposite) if amount < 0 => Some(target.remove(opposite)),
+        Some(_) => Some(target.add(amount)),
+    }
+}
+
+/// Removes stores to fake players overwritten before being read, going backwards from the end
+/// with the scores nothing reads from that point on.
+fn eliminate_dead_stores(body: Vec<Qualified>, codegen: &Codegen) -> Vec<Qualified> {
+    let mut dead = body
+        .iter()
+        .flat_map(|qualified| mentioned(qualified.command()))
+        .filter(|key| is_register(key, codegen))
+        .collect::<HashSet<String>>();
+    let mut res = Vec::new();
+    for qualified in body.into_iter().rev() {
+        let unconditional = qualified.conditions().is_empty();
+        let effect = Effect::of(&qualified);
+        // An operation sets an unset source to 0, which is only known not to matter for
+        // the registers and constants.
+        let creates = matches!(&effect, Effect::Operation(_, _, source) if source.objective != codegen.objective);
+        let (written, overwrites, mut reads) = match &effect {
+            Effect::Set(score, _) | Effect::Reset(score) => (Some(score), true, vec![]),
+            Effect::Add(score, _) => (Some(score), false, vec![Codegen::key(score)]),
+            Effect::Operation(target, operation, source) => {
+                let mut reads = vec![Codegen::key(source)];
+                if operation != "=" {
+                    reads.push(Codegen::key(target));
+                }
+                // A swap writes the source as well, so it's never dead.
+                let written = (operation != "><").then_some(target);
+                (written, operation == "=", reads)
+            }
+            Effect::Opaque => (None, false, mentioned(qualified.command())),
+        };
+        if let Some(score) = written
+            && score.scoreholder.starts_with('#')
+            && dead.contains(&Codegen::key(score))
+            && !creates
+        {
+            continue;
+        }
+        if matches!(effect, Effect::Opaque) {
+            // Registers are only read within the instruction which set them, so nothing else sees them.
+            dead.retain(|key| is_register(key, codegen));
+        } else if let Some(score) = written.filter(|_| overwrites && unconditional) {
+            dead.insert(Codegen::key(score));
+        }
+        reads.extend(qualified.conditions().into_iter().flat_map(mentioned));
+        for key in reads {
+            dead.remove(&key);
+        }
+        res.push(qualified);
+    }
+    res.reverse();
+    res
+}
+
+/// An operation folded by its mnemonic, taking the value of the target and of the source.
+#[derive(Clone, Copy)]
+pub(crate) enum Operation {
+    Mov,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Sur,
+    Min,
+    Max,
+    And,
+    Or,
+    Xor,
+    Shl,
+    Shr,
+}
+
+impl Operation {
+    /// The value the target ends up with, as the commands compute it.
+    /// `None` for dividing by zero, which is left for the game to deal with.
+    pub(crate) fn apply(self, target: i32, source: i32) -> Option<i32> {
+        Some(match self {
+            Self::Mov => source,
+            Self::Add => target.wrapping_add(source),
+            Self::Sub => target.wrapping_sub(source),
+            Self::Mul => target.wrapping_mul(source),
+            // Floor division and floor modulo, as scoreboards do.
+            Self::Div if source != 0 => {
+                let quotient = target.wrapping_div(source);
+                let inexact = target.wrapping_rem(source) != 0;
+                if inexact && (target < 0) != (source < 0) {
+                    quotient - 1
+                } else {
+                    quotient
+                }
+            }
+            Self::Sur if source != 0 => {
+                let remainder = target.wrapping_rem(source);
+                if remainder != 0 && (remainder < 0) != (source < 0) {
+                    remainder + source
+                } else {
+                    remainder
+                }
+            }
+            Self::Div | Self::Sur => return None,
+            Self::Min => target.min(source),
+            Self::Max => target.max(source),
+            Self::And => target & source,
+            Self::Or => target | source,
+            Self::Xor => target ^ source,
+            Self::Shl => match source {
+                ..=0 => target,
+                32.. => 0,
+                amount => target << amount,
+            },
+            Self::Shr => target >> source.clamp(0, 31),
+        })
+    }
+    fn mnemonic(self, target: Scoreboard, source: IntLiteral) -> Mnemonic {
+        let source = Operand::Int(source);
+        match self {
+            Self::Mov => Mnemonic::Mov((target, source)),
+            Self::Add => Mnemonic::Add((target, source)),
+            Self::Sub => Mnemonic::Sub((target, source)),
+            Self::Mul => Mnemonic::Mul((target, source)),
+            Self::Div => Mnemonic::Div((target, source)),
+            Self::Sur => Mnemonic::Sur((target, source)),
+            Self::Min => Mnemonic::Min((target, source)),
+            Self::Max => Mnemonic::Max((target, source)),
+            Self::And => Mnemonic::And((target, source)),
+            Self::Or => Mnemonic::Or((target, source)),
+            Self::Xor => Mnemonic::Xor((target, source)),
+            Self::Shl => Mnemonic::Shl((target, source)),
+            Self::Shr => Mnemonic::Shr((target, source)),
+        }
+    }
+    /// The operation of a mnemonic taking a target and a source, with both of them.
+    pub(crate) fn of(mnemonic: &Mnemonic) -> Option<(Self, &Scoreboard, OperandRef<'_>)> {
+        let (operation, target, source) = match mnemonic {
+            Mnemonic::Def((target, source)) | Mnemonic::Mov((target, source)) => {
+                (Self::Mov, target, source.as_operand())
+            }
+            Mnemonic::Add((target, source)) => (Self::Add, target, source.as_operand()),
+            Mnemonic::Sub((target, source)) => (Self::Sub, target, source.as_operand()),
+            Mnemonic::Mul((target, source)) => (Self::Mul, target, source.as_operand()),
+            Mnemonic::Div((target, source)) => (Self::Div, target, source.as_operand()),
+            Mnemonic::Sur((target, source)) => (Self::Sur, target, source.as_operand()),
+            Mnemonic::Min((target, source)) => (Self::Min, target, source.as_operand()),
+            Mnemonic::Max((target, source)) => (Self::Max, target, source.as_operand()),
+            Mnemonic::And((target, source)) => (Self::And, target, source.as_operand()),
+            Mnemonic::Or((target, source)) => (Self::Or, target, source.as_operand()),
+            Mnemonic::Xor((target, source)) => (Self::Xor, target, source.as_operand()),
+            Mnemonic::Shl((target, source)) => (Self::Shl, target, source.as_operand()),
+            Mnemonic::Shr((target, source)) => (Self::Shr, target, source.as_operand()),
+            _ => return None,
+        };
+        Some((operation, target, source))
+    }
+}
+
+impl From<Operator> for Operation {
+    fn from(operator: Operator) -> Self {
+        match operator {
+            Operator::Add => Self::Add,
+            Operator::Sub => Self::Sub,
+            Operator::Mul => Self::Mul,
+            Operator::Div => Self::Div,
+            Operator::Sur => Self::Sur,
+        }
+    }
+}
+
+/// Values of the fake players known at a point of the program.
+struct Constants {
+    values: HashMap<String, i32>,
+    /// Scores whose value isn't what the commands hold, declared with `.fixed` or `.long`.
+    untracked: HashSet<String>,
+    /// Scores declared with `.long`, whose comparisons set an unset score compared with them to 0.
+    longs: HashSet<String>,
+    /// Objective of the registers, which the compiled commands write behind the mnemonics.
+    objective: String,
+}
+
+impl Constants {
+    fn is_tracked(&self, score: &Scoreboard) -> bool {
+        score.scoreholder.starts_with('#')
+            && score.objective != self.objective
+            && !self.untracked.contains(&Codegen::key(score))
+    }
+    fn get(&self, score: &Scoreboard) -> Option<i32> {
+        self.values.get(&Codegen::key(score)).copied()
+    }
+    /// The value of an operand, which is a literal fitting into a score or a score known to hold it.
+    /// A literal which doesn't fit is left as it is, to be reported when it's compiled.
+    fn operand(&self, operand: OperandRef) -> Option<i32> {
+        match operand {
+            OperandRef::Int(int) => i32::try_from(int.value()).ok(),
+            OperandRef::Score(score) => self.get(score),
+            _ => None,
+        }
+    }
+    fn bound(&self, bound: Option<&Bound>) -> Option<Option<i64>> {
+        match bound {
+            None => Some(None),
+            Some(Bound::Int(int)) => self.operand(OperandRef::Int(int)).map(|v| Some(v.into())),
+            Some(Bound::Score(score)) => self.get(score).map(|v| Some(v.into())),
+        }
+    }
+    /// Whether `lhs <comparison> rhs` holds, when both sides are known.
+    fn compare(&self, comparison: Comparison, lhs: &Scoreboard, rhs: OperandRef) -> Option<bool> {
+        let lhs = i64::from(self.get(lhs)?);
+        let (min, max) = match rhs {
+            OperandRef::Range(range) => {
+                let fits = |bound: Option<i64>| bound.is_none_or(|b| i32::try_from(b).is_ok());
+                if !fits(range.min()) || !fits(range.max()) {
+                    return None;
+                }
+                (range.min(), range.max())
+            }
+            OperandRef::ScoreRange(range) => (self.bound(range.min())?, self.bound(range.max())?),
+            _ => {
+                let rhs = i64::from(self.operand(rhs)?);
+                return Some(match comparison {
+                    Comparison::Eq | Comparison::In => lhs == rhs,
+                    Comparison::Lt => lhs < rhs,
+                    Comparison::Le => lhs <= rhs,
+                    Comparison::Gt => lhs > rhs,
+                    Comparison::Ge => lhs >= rhs,
+                });
+            }
+        };
+        Some(min.is_none_or(|min| min <= lhs) && max.is_none_or(|max| lhs <= max))
+    }
+    /// Whether a clause of `IF` or the comparison of a conditional jump holds.
+    fn holds(
+        &self,
+        unless: bool,
+        comparison: Comparison,
+        lhs: &Scoreboard,
+        rhs: OperandRef,
+    ) -> Option<bool> {
+        self.compare(comparison, lhs, rhs)
+            .map(|holds| holds != unless)
+    }
+    fn evaluate(&self, expression: &Expression) -> Option<i32> {
+        match expression {
+            Expression::Score(score) => self.get(score),
+            Expression::Int(int) => self.operand(OperandRef::Int(int)),
+            Expression::Negate(operand) => Some(self.evaluate(operand)?.wrapping_neg()),
+            Expression::Binary((lhs, operator, rhs)) => {
+                Operation::from(*operator).apply(self.evaluate(lhs)?, self.evaluate(rhs)?)
+            }
+        }
+    }
+    /// Forgets the scores the mnemonic may write, every holder of the objective for `*`.
+    fn forget(&mut self, mnemonic: &Mnemonic) {
+        for score in written(mnemonic) {
+            if score.scoreholder == "*" {
+                let prefix = Codegen::key(&Scoreboard::new("", score.objective.clone()));
+                self.values.retain(|key, _| !key.starts_with(&prefix));
+            } else {
+                self.values.remove(&Codegen::key(score));
+            }
+        }
+    }
+}
+
+/// Every score the mnemonic may write, including the ones of the mnemonic it guards.
+fn written(mnemonic: &Mnemonic) -> Vec<&Scoreboard> {
+    if let Some((_, target, _)) = Operation::of(mnemonic) {
+        return vec![target];
+    }
+    match mnemonic {
+        Mnemonic::Swp((score, other)) => vec![score, other],
+        Mnemonic::Neg(score)
+        | Mnemonic::Abs(score)
+        | Mnemonic::Not(score)
+        | Mnemonic::Let((score, _))
+        | Mnemonic::Nts((score, ..))
+        | Mnemonic::Rel(score) => vec![score],
+        Mnemonic::If((_, guarded)) => written(guarded),
+        Mnemonic::Execute((modifier, guarded)) => {
+            let mut scores = written(guarded);
+            scores.extend(modifier.scoreboard());
+            scores
+        }
+        _ => match mnemonic.comparison() {
+            Some((.., guarded)) => written(guarded),
+            None => vec![],
+        },
+    }
+}
+
+/// The mnemonic a conditional jump or `IF` guards.
+fn guarded(mnemonic: Mnemonic) -> Option<Mnemonic> {
+    match mnemonic {
+        Mnemonic::Je((_, _, guarded))
+        | Mnemonic::Jne((_, _, guarded))
+        | Mnemonic::Jl((_, _, guarded))
+        | Mnemonic::Jg((_, _, guarded))
+        | Mnemonic::Jle((_, _, guarded))
+        | Mnemonic::Jge((_, _, guarded))
+        | Mnemonic::Jin((_, _, guarded))
+        | Mnemonic::Jnl((_, _, guarded))
+        | Mnemonic::Jng((_, _, guarded))
+        | Mnemonic::Jnle((_, _, guarded))
+        | Mnemonic::Jnge((_, _, guarded))
+        | Mnemonic::Jnin((_, _, guarded))
+        | Mnemonic::If((_, guarded)) => Some(*guarded),
+        _ => None,
+    }
+}
+
+/// Folds the mnemonics by the values of the fake players known at compile time, e.g. `#A` holding 11 after
+/// `MOV #B 5`, `MOV #C 6`, `MOV #A #B` and `ADD #A #C`.
+///
+/// - An operation with both sides known becomes `MOV` of the result, and a known source becomes a literal.
+/// - `MOV` of the value the score already holds is removed.
+/// - A conditional jump or `IF` whose condition is known is replaced by the mnemonic it guards, or removed.
+///
+/// Values are only known within a block, as a label may be jumped to from anywhere.
+/// Scores declared with `.fixed` or `.long`, and the ones of the registers' objective, aren't folded.
+pub fn fold(mnemonics: Vec<Mnemonic>, codegen: &Codegen) -> Vec<Mnemonic> {
+    let mut untracked = HashSet::new();
+    let mut longs = HashSet::new();
+    for mnemonic in &mnemonics {
+        match mnemonic {
+            Mnemonic::Fixed((score, _)) => {
+                untracked.insert(Codegen::key(score));
+            }
+            Mnemonic::Long(score) => {
+                let register = LongRegister::of(score);
+                untracked.extend([score, &register.hi, &register.lo].map(Codegen::key));
+                longs.insert(Codegen::key(score));
+            }
+            _ => {}
+        }
+    }
+    let mut constants = Constants {
+        values: HashMap::new(),
+        untracked,
+        longs,
+        objective: codegen.objective.clone(),
+    };
+    mnemonics
+        .into_iter()
+        .filter_map(|mnemonic| fold_one(mnemonic, &mut constants))
+        .collect()
+}
+
+fn fold_one(mnemonic: Mnemonic, constants: &mut Constants) -> Option<Mnemonic> {
+    if let Mnemonic::Label(_) = mnemonic {
+        constants.values.clear();
+        return Some(mnemonic);
+    }
+    let decided = match &mnemonic {
+        // Every clause is compared before the mnemonic runs, so one known to hold doesn't
+        // decide the others' commands away when those may set a score.
+        Mnemonic::If((disjunction, _))
+            if disjunction.iter().flatten().any(|clause| {
+                [Some(&clause.lhs), clause.rhs.as_operand().scoreboard()]
+                    .into_iter()
+                    .flatten()
+                    .any(|score| constants.longs.contains(&Codegen::key(score)))
+            }) =>
+        {
+            None
+        }
+        Mnemonic::If((disjunction, _)) => {
+            let clause = |clause: &Clause| {
+                constants.holds(
+                    clause.unless,
+                    clause.comparison,
+                    &clause.lhs,
+                    clause.rhs.as_operand(),
+                )
+            };
+            let conjunctions = disjunction
+                .iter()
+                .map(|conjunction| {
+                    let clauses = conjunction.iter().map(clause).collect::<Vec<_>>();
+                    if clauses.contains(&Some(false)) {
+                        Some(false)
+                    } else {
+                        clauses
+                            .into_iter()
+                            .collect::<Option<Vec<bool>>>()
+                            .map(|_| true)
+                    }
+                })
+                .collect::<Vec<_>>();
+            if conjunctions.contains(&Some(true)) {
+                Some(true)
+            } else {
+                conjunctions
+                    .into_iter()
+                    .collect::<Option<Vec<bool>>>()
+                    .map(|_| false)
+            }
+        }
+        _ => mnemonic
+            .comparison()
+            .and_then(|(unless, comparison, lhs, rhs, _)| {
+                constants.holds(unless, comparison, lhs, rhs.as_operand())
+            }),
+    };
+    match decided {
+        Some(true) => return guarded(mnemonic).and_then(|guarded| fold_one(guarded, constants)),
+        Some(false) => return None,
+        None => {}
+    }
+
+    if let Some((operation, target, source)) = Operation::of(&mnemonic)
+        && constants.is_tracked(target)
+    {
+        let span = match source {
+            OperandRef::Int(int) => int.span(),
+            _ => target.span,
+        };
+        let key = Codegen::key(target);
+        let held = constants.get(target);
+        let folded = constants.operand(source).map(|source| {
+            let result = match (operation, held) {
+                (Operation::Mov, _) => Some(source),
+                (_, Some(held)) => operation.apply(held, source),
+                (_, None) => None,
+            };
+            (source, result)
+        });
+        return match folded {
+            // Leaving the value it already holds, e.g. setting it again.
+            Some((_, Some(result))) if held == Some(result) => None,
+            Some((_, Some(result))) => {
+                constants.values.insert(key, result);
+                Some(Operation::Mov.mnemonic(target.clone(), IntLiteral::new(result.into(), span)))
+            }
+            // Shifting by a literal of 0 or less compiles into nothing, while a score sets an unset target to 0.
+            Some((source, None))
+                if matches!(operation, Operation::Shl | Operation::Shr) && source <= 0 =>
+            {
+                Some(mnemonic)
+            }
+            Some((source, None)) => {
+                constants.values.remove(&key);
+                Some(operation.mnemonic(target.clone(), IntLiteral::new(source.into(), span)))
+            }
+            None => {
+                constants.values.remove(&key);
+                Some(mnemonic)
+            }
+        };
+    }
+
+    let folded = match &mnemonic {
+        Mnemonic::Neg(score) => constants
+            .get(score)
+            .map(|value| (score, value.wrapping_neg())),
+        Mnemonic::Abs(score) => constants
+            .get(score)
+            .map(|value| (score, value.wrapping_abs())),
+        Mnemonic::Not(score) => constants.get(score).map(|value| (score, !value)),
+        Mnemonic::Let((score, expression)) => {
+            constants.evaluate(expression).map(|value| (score, value))
+        }
+        _ => None,
+    }
+    .filter(|(score, _)| constants.is_tracked(score));
+    if let Some((score, value)) = folded {
+        let score = score.clone();
+        constants.values.insert(Codegen::key(&score), value);
+        let span = score.span;
+        return Some(Operation::Mov.mnemonic(score, IntLiteral::new(value.into(), span)));
+    }
+
+    if let Mnemonic::Swp((score, other)) = &mnemonic
+        && constants.is_tracked(score)
+        && constants.is_tracked(other)
+    {
+        let (a, b) = (constants.get(score), constants.get(other));
+        for (score, value) in [(score, b), (other, a)] {
+            match value {
+                Some(value) => constants.values.insert(Codegen::key(score), value),
+                None => constants.values.remove(&Codegen::key(score)),
+            };
+        }
+        return Some(mnemonic);
+    }
+    constants.forget(&mnemonic);
+    Some(mnemonic)
+}
@@ -1,162 +1,653 @@
 use crate::types::*;
 
+fn too_few_operand(mnemonic: &Token, expected: usize, tokens: &[Token]) -> MCAsmError {
+    MCAsmError::TooFewOperand {
+        mnemonic: mnemonic.clone(),
+        expected,
+        found: tokens.len(),
+    }
+}
+
+/// A label, which can't be under `mc_asm/` where the helper functions are.
+fn label(token: &Token) -> Result<Token, MCAsmError> {
+    let is_valid = |c: char| matches!(c, 'a'..='z' | '0'..='9' | '_' | '-' | '.' | '/');
+    if !token.text.is_empty()
+        && token.text.chars().all(is_valid)
+        && !token.text.starts_with("mc_asm/")
+    {
+        Ok(token.clone())
+    } else {
+        Err(MCAsmError::InvalidLabel(token.clone()))
+    }
+}
+
+/// A number with a fraction like `1.5`. Integers are left to `IntLiteral::try_from`.
+fn flt_literal(token: &Token) -> Option<FltLiteral> {
+    token
+        .text
+        .contains('.')
+        .then(|| token.text.parse::<f64>().ok())
+        .flatten()
+        .filter(|float| float.is_finite())
+        .map(|float| FltLiteral::new(float, token.span))
+}
+
 fn score_and_score_or_integer(
-    splitten_at_1: Option<&&str>,
-    splitten_at_2: Option<&&str>,
-    score_score_closure: fn(Scoreboard, Scoreboard) -> Mnemonic,
-    score_int_closure: fn(Scoreboard, i64) -> Mnemonic,
+    mnemonic: &Token,
+    tokens: &[Token],
+    closure: fn((Scoreboard, Operand)) -> Mnemonic,
 ) -> Result<Mnemonic, MCAsmError> {
-    if let (Some(operand1), Some(operand2)) = (splitten_at_1, splitten_at_2) {
-        match (
-            Scoreboard::try_from(operand1),
-            Scoreboard::try_from(operand2),
-            operand2.parse::<i64>(),
-        ) {
-            (Ok(score1), _, Ok(int_literal)) => Ok(score_int_closure(score1, int_literal)),
-            (Ok(score1), Ok(score2), Err(_)) => Ok(score_score_closure(score1, score2)),
-            _ => Err(MCAsmError::InvalidOperand),
+    if let [operand1, operand2, ..] = tokens {
+        let score1 = Scoreboard::try_from(operand1)?;
+        if let Some(int_literal) = IntLiteral::try_from(operand2) {
+            return Ok(closure((score1, Operand::Int(int_literal))));
+        }
+        if let Some(flt_literal) = flt_literal(operand2) {
+            return Ok(closure((score1, Operand::Float(flt_literal))));
         }
+        match Scoreboard::try_from(operand2) {
+            Ok(score2) => Ok(closure((score1, Operand::Score(score2)))),
+            _ => Err(MCAsmError::InvalidOperand {
+                expected: "`objective::holder` or a number",
+                found: operand2.clone(),
+            }),
+        }
+    } else {
+        Err(too_few_operand(mnemonic, 2, tokens))
+    }
+}
+
+/// A scoreboard and a source which is a scoreboard or an integer, for mnemonics which can't take a fraction.
+fn score_and_score_or_integer_only(
+    mnemonic: &Token,
+    tokens: &[Token],
+    closure: fn((Scoreboard, Operand)) -> Mnemonic,
+) -> Result<Mnemonic, MCAsmError> {
+    if let [operand1, operand2, ..] = tokens {
+        let score = Scoreboard::try_from(operand1)?;
+        Ok(closure((score, score_or_integer(operand2)?)))
     } else {
-        Err(MCAsmError::TooFewOperand)
+        Err(too_few_operand(mnemonic, 2, tokens))
     }
 }
 
-fn nbtstorage_to_score(
-    operands: (Option<&&str>, Option<&&str>, Option<&&str>, Option<&&str>),
+/// `SHL` and `SHR`, whose amount can't be negative when it's a literal.
+fn shift(
+    mnemonic: &Token,
+    tokens: &[Token],
+    closure: fn((Scoreboard, Operand)) -> Mnemonic,
 ) -> Result<Mnemonic, MCAsmError> {
-    if let (Some(s1), Some(s2), Some(s3), Some(s4)) = operands {
+    if let [_, amount, ..] = tokens
+        && IntLiteral::try_from(amount).is_some_and(|amount| amount.value() < 0)
+    {
+        return Err(MCAsmError::InvalidOperand {
+            expected: "`objective::holder` or an amount from 0",
+            found: amount.clone(),
+        });
+    }
+    score_and_score_or_integer_only(mnemonic, tokens, closure)
+}
+
+fn nbtstorage_to_score(mnemonic: &Token, tokens: &[Token]) -> Result<Mnemonic, MCAsmError> {
+    if let [s1, s2, s3, s4, ..] = tokens {
         let score = Scoreboard::try_from(s1)?;
         let storage = Storage::try_from(s2)?;
         let path = Path::try_from(s3)?;
         let magnif = s4
+            .text
             .parse::<u32>()
-            .or(Err(MCAsmError::CantImplicateAsUnsignedInteger))?;
+            .or(Err(MCAsmError::CantImplicateAsUnsignedInteger(s4.clone())))?;
 
         Ok(Mnemonic::Nts((score, storage, path, magnif)))
     } else {
-        Err(MCAsmError::TooFewOperand)
+        Err(too_few_operand(mnemonic, 4, tokens))
     }
 }
 
-fn score_to_nbtstorage(
-    operands: (Option<&&str>, Option<&&str>, Option<&&str>, Option<&&str>),
-) -> Result<Mnemonic, MCAsmError> {
-    if let (Some(s1), Some(s2), Some(s3), Some(s4)) = operands {
+fn score_to_nbtstorage(mnemonic: &Token, tokens: &[Token]) -> Result<Mnemonic, MCAsmError> {
+    if let [s1, s2, s3, s4, ..] = tokens {
         let storage = Storage::try_from(s1)?;
         let path = Path::try_from(s2)?;
         let score = Scoreboard::try_from(s3)?;
-        let magnif = s4
-            .parse::<f32>()
-            .or(Err(MCAsmError::CantImplicateAsUnsignedInteger))?;
+        let magnif = s4.text.parse::<f32>().or(Err(MCAsmError::InvalidOperand {
+            expected: "a number",
+            found: s4.clone(),
+        }))?;
         Ok(Mnemonic::Stn((storage, path, score, magnif)))
     } else {
-        Err(MCAsmError::TooFewOperand)
+        Err(too_few_operand(mnemonic, 4, tokens))
+    }
+}
+
+fn score_or_integer(token: &Token) -> Result<Operand, MCAsmError> {
+    match (Scoreboard::try_from(token), IntLiteral::try_from(token)) {
+        (Ok(score), _) => Ok(Operand::Score(score)),
+        (_, Some(int)) => Ok(Operand::Int(int)),
+        (_, _) => Err(MCAsmError::InvalidOperand {
+            expected: "`objective::holder` or an integer",
+            found: token.clone(),
+        }),
+    }
+}
+
+fn range(token: &Token) -> Result<Operand, MCAsmError> {
+    if let Some(range) = RangeLiteral::try_from(token)? {
+        return Ok(Operand::Range(range));
+    }
+    let index = token
+        .text
+        .find("..")
+        .ok_or_else(|| MCAsmError::InvalidOperand {
+            expected: "a range like `1..10`, `..4` or `mcasm::#lo..mcasm::#hi`",
+            found: token.clone(),
+        })?;
+    let bound = |bound: Token| {
+        if bound.text.is_empty() {
+            Ok(None)
+        } else if let Some(int) = IntLiteral::try_from(&bound) {
+            Ok(Some(Bound::Int(int)))
+        } else {
+            Scoreboard::try_from(&bound)
+                .map(|score| Some(Bound::Score(score)))
+                .map_err(|_| MCAsmError::InvalidOperand {
+                    expected: "`objective::holder` or an integer",
+                    found: bound,
+                })
+        }
+    };
+    Ok(Operand::ScoreRange(ScoreRange::new(
+        bound(token.slice(0..index))?,
+        bound(token.slice(index + 2..token.text.len()))?,
+        token.span,
+    )))
+}
+
+/// The mnemonic a condition guards. A lone token which isn't a mnemonic is a label,
+/// as a shorthand of `JMP label`, while a lone mnemonic is missing its operands.
+fn guarded(mnemonic: &Token, tokens: &[Token]) -> Result<Mnemonic, MCAsmError> {
+    match parse_tokens(mnemonic, tokens) {
+        Err(MCAsmError::UnknownMnemonic(_)) if tokens.is_empty() => {
+            Ok(Mnemonic::Jmp(label(mnemonic)?))
+        }
+        parsed => parsed,
     }
 }
 
 fn compare(
-    lhs: Option<&&str>,
-    rhs: Option<&&str>,
-    then_mnemonic: Option<&&str>,
-    then_operands: Option<&[&str]>,
-    score_score_closure: fn(Scoreboard, Scoreboard, Mnemonic) -> Mnemonic,
-    score_int_closure: fn(Scoreboard, i64, Mnemonic) -> Mnemonic,
+    mnemonic: &Token,
+    tokens: &[Token],
+    rhs: fn(&Token) -> Result<Operand, MCAsmError>,
+    closure: fn(Comparing) -> Mnemonic,
 ) -> Result<Mnemonic, MCAsmError> {
-    if let (Some(s1), Some(s2), Some(then_mnemonic), Some(then_operands)) =
-        (lhs, rhs, then_mnemonic, then_operands)
-    {
+    if let [s1, s2, then_mnemonic, then_operands @ ..] = tokens {
         let score = Scoreboard::try_from(s1)?;
-        Ok(match (Scoreboard::try_from(s2), s2.parse::<i64>()) {
-            (Ok(other), _) => {
-                score_score_closure(score, other, parse_tokens(then_mnemonic, then_operands)?)
+        let other = rhs(s2)?;
+        let then = guarded(then_mnemonic, then_operands)?;
+        Ok(closure((score, other, Box::new(then))))
+    } else {
+        Err(too_few_operand(mnemonic, 3, tokens))
+    }
+}
+
+fn condition(mnemonic: &Token, tokens: &[Token]) -> Result<Mnemonic, MCAsmError> {
+    let mut disjunction = vec![Vec::new()];
+    let mut rest = tokens;
+    loop {
+        let [lhs, operator, rhs, next, after @ ..] = rest else {
+            return Err(too_few_operand(
+                mnemonic,
+                tokens.len() - rest.len() + 5,
+                tokens,
+            ));
+        };
+        let (unless, comparison, parse_rhs): (bool, Comparison, fn(&Token) -> _) =
+            match operator.text.as_str() {
+                "==" => (false, Comparison::Eq, score_or_integer),
+                "!=" => (true, Comparison::Eq, score_or_integer),
+                "<" => (false, Comparison::Lt, score_or_integer),
+                "<=" => (false, Comparison::Le, score_or_integer),
+                ">" => (false, Comparison::Gt, score_or_integer),
+                ">=" => (false, Comparison::Ge, score_or_integer),
+                "in" => (false, Comparison::In, range),
+                "!in" => (true, Comparison::In, range),
+                _ => {
+                    return Err(MCAsmError::InvalidOperand {
+                        expected: "a comparison operator like `==`, `<` or `in`",
+                        found: operator.clone(),
+                    });
+                }
+            };
+        let clause = Clause {
+            unless,
+            comparison,
+            lhs: Scoreboard::try_from(lhs)?,
+            rhs: parse_rhs(rhs)?,
+        };
+        if let Some(conjunction) = disjunction.last_mut() {
+            conjunction.push(clause);
+        }
+        match next.text.as_str() {
+            "&&" => {}
+            "||" => disjunction.push(Vec::new()),
+            "THEN" => {
+                return match after {
+                    [then_mnemonic, then_operands @ ..] => Ok(Mnemonic::If((
+                        disjunction,
+                        Box::new(guarded(then_mnemonic, then_operands)?),
+                    ))),
+                    [] => Err(too_few_operand(mnemonic, tokens.len() + 1, tokens)),
+                };
+            }
+            _ => {
+                return Err(MCAsmError::InvalidOperand {
+                    expected: "`&&`, `||` or `THEN`",
+                    found: next.clone(),
+                });
+            }
+        }
+        rest = after;
+    }
+}
+
+fn arguments<'a, const N: usize>(
+    mnemonic: &Token,
+    tokens: &'a [Token],
+) -> Result<([Token; N], &'a [Token]), MCAsmError> {
+    if tokens.len() < N {
+        return Err(too_few_operand(mnemonic, N + 1, tokens));
+    }
+    let (taken, rest) = tokens.split_at(N);
+    Ok((std::array::from_fn(|i| taken[i].clone()), rest))
+}
+
+/// An entity argument of a modifier, validated when it's a selector.
+fn entity(token: &Token) -> Result<Token, MCAsmError> {
+    if token.text.starts_with('@') {
+        Selector::parse(token)?;
+    }
+    Ok(token.clone())
+}
+
+/// A modifier wrapping the mnemonic following its arguments.
+fn execute(
+    mnemonic: &Token,
+    tokens: &[Token],
+    modifier: Modifier,
+    rest: &[Token],
+) -> Result<Mnemonic, MCAsmError> {
+    match rest {
+        [wrapped, operands @ ..] => Ok(Mnemonic::Execute((
+            modifier,
+            Box::new(parse_tokens(wrapped, operands)?),
+        ))),
+        [] => Err(too_few_operand(mnemonic, tokens.len() + 1, tokens)),
+    }
+}
+
+fn modifier(mnemonic: &Token, tokens: &[Token]) -> Result<Mnemonic, MCAsmError> {
+    let (modifier, rest) = match mnemonic.text.as_str() {
+        "AS" => {
+            let ([selector], rest) = arguments(mnemonic, tokens)?;
+            (Modifier::As(entity(&selector)?), rest)
+        }
+        "AT" => {
+            let ([selector], rest) = arguments(mnemonic, tokens)?;
+            (Modifier::At(entity(&selector)?), rest)
+        }
+        "POSITIONED" => {
+            let (position, rest) = arguments(mnemonic, tokens)?;
+            (Modifier::Positioned(position), rest)
+        }
+        "IN" => {
+            let ([dimension], rest) = arguments(mnemonic, tokens)?;
+            (Modifier::In(dimension), rest)
+        }
+        "STORE" => {
+            let [kind, target, rest @ ..] = tokens else {
+                return Err(too_few_operand(mnemonic, 3, tokens));
+            };
+            let success = match kind.text.as_str() {
+                "RESULT" => false,
+                "SUCCESS" => true,
+                _ => {
+                    return Err(MCAsmError::InvalidOperand {
+                        expected: "`RESULT` or `SUCCESS`",
+                        found: kind.clone(),
+                    });
+                }
+            };
+            match Storage::try_from(target) {
+                Ok(storage) => {
+                    let [path, magnif, rest @ ..] = rest else {
+                        return Err(too_few_operand(mnemonic, 5, tokens));
+                    };
+                    let magnif =
+                        magnif
+                            .text
+                            .parse::<f32>()
+                            .or(Err(MCAsmError::InvalidOperand {
+                                expected: "a number",
+                                found: magnif.clone(),
+                            }))?;
+                    let target = StoreTarget::Storage((storage, Path::try_from(path)?, magnif));
+                    (Modifier::Store { success, target }, rest)
+                }
+                Err(_) => {
+                    let target = StoreTarget::Score(Scoreboard::try_from(target)?);
+                    (Modifier::Store { success, target }, rest)
+                }
             }
-            (_, Ok(other)) => {
-                score_int_closure(score, other, parse_tokens(then_mnemonic, then_operands)?)
+        }
+        // `IF` or `UNLESS`
+        _ => {
+            let unless = mnemonic.text == "UNLESS";
+            let [kind, tokens @ ..] = tokens else {
+                return Err(too_few_operand(mnemonic, 3, tokens));
+            };
+            match kind.text.as_str() {
+                "ENTITY" => {
+                    let ([selector], rest) = arguments(mnemonic, tokens)?;
+                    let selector = entity(&selector)?;
+                    (Modifier::Entity { unless, selector }, rest)
+                }
+                "BLOCK" => {
+                    let ([x, y, z, block], rest) = arguments(mnemonic, tokens)?;
+                    let position = [x, y, z];
+                    (
+                        Modifier::Block {
+                            unless,
+                            position,
+                            block,
+                        },
+                        rest,
+                    )
+                }
+                "DATA" => {
+                    let [source, tokens @ ..] = tokens else {
+                        return Err(too_few_operand(mnemonic, 4, tokens));
+                    };
+                    let (source, tokens) = match source.text.as_str() {
+                        "BLOCK" => {
+                            let (position, rest) = arguments(mnemonic, tokens)?;
+                            (DataSource::Block(position), rest)
+                        }
+                        "ENTITY" => {
+                            let ([selector], rest) = arguments(mnemonic, tokens)?;
+                            (DataSource::Entity(entity(&selector)?), rest)
+                        }
+                        "STORAGE" => match tokens {
+                            [storage, rest @ ..] => {
+                                (DataSource::Storage(Storage::try_from(storage)?), rest)
+                            }
+                            [] => return Err(too_few_operand(mnemonic, 4, tokens)),
+                        },
+                        _ => {
+                            return Err(MCAsmError::InvalidOperand {
+                                expected: "`BLOCK`, `ENTITY` or `STORAGE`",
+                                found: source.clone(),
+                            });
+                        }
+                    };
+                    let ([path], rest) = arguments(mnemonic, tokens)?;
+                    (
+                        Modifier::Data {
+                            unless,
+                            source,
+                            path,
+                        },
+                        rest,
+                    )
+                }
+                _ => {
+                    return Err(MCAsmError::InvalidOperand {
+                        expected: "`ENTITY`, `BLOCK` or `DATA`",
+                        found: kind.clone(),
+                    });
+                }
             }
-            (_, _) => Err(MCAsmError::InvalidOperand)?,
-        })
+        }
+    };
+    execute(mnemonic, tokens, modifier, rest)
+}
+
+fn jump(mnemonic: &Token, tokens: &[Token]) -> Result<Mnemonic, MCAsmError> {
+    if let [s1, ..] = tokens {
+        Ok(Mnemonic::Jmp(label(s1)?))
     } else {
-        Err(MCAsmError::TooFewOperand)
+        Err(too_few_operand(mnemonic, 1, tokens))
     }
 }
 
-fn release(operand: Option<&&str>) -> Result<Mnemonic, MCAsmError> {
-    if let Some(s1) = operand {
-        Ok(Mnemonic::Rel(Box::new(Scoreboard::try_from(s1)?)))
+/// The display name is the rest of the line as it's written, spaces included.
+fn objective(mnemonic: &Token, tokens: &[Token], line: &str) -> Result<Mnemonic, MCAsmError> {
+    if let [name, criteria, display_name @ ..] = tokens {
+        let is_valid = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+');
+        if name.text.len() > 16 || !name.text.chars().all(is_valid) {
+            return Err(MCAsmError::InvalidOperand {
+                expected: "an objective name of up to 16 letters, digits, `_`, `-`, `.` or `+`",
+                found: name.clone(),
+            });
+        }
+        Ok(Mnemonic::Objective(Objective {
+            name: name.clone(),
+            criteria: criteria.text.clone(),
+            display_name: display_name
+                .first()
+                .zip(display_name.last())
+                .map(|(first, last)| line[first.span.start..last.span.end].to_string()),
+        }))
     } else {
-        Err(MCAsmError::TooFewOperand)
+        Err(too_few_operand(mnemonic, 2, tokens))
     }
 }
 
-pub fn parse_tokens(mnemonic_kind: &str, tokens: &[&str]) -> Result<Mnemonic, MCAsmError> {
-    macro_rules! score_source_mnemonic {
-        ($x:path) => {
-            score_and_score_or_integer(
-                tokens.get(0),
-                tokens.get(1),
-                |a: Scoreboard, b: Scoreboard| -> Mnemonic { $x((a, Box::new(b))) },
-                |a: Scoreboard, b: i64| -> Mnemonic { $x((a, Box::new(IntLiteral::from(b)))) },
-            )
-        };
+fn fixed(mnemonic: &Token, tokens: &[Token]) -> Result<Mnemonic, MCAsmError> {
+    if let [score, scale, ..] = tokens {
+        let scale = IntLiteral::try_from(scale)
+            .filter(|scale| (1..=i32::MAX as i64).contains(&scale.value()))
+            .ok_or_else(|| MCAsmError::InvalidOperand {
+                expected: "a positive scale like `1000`",
+                found: scale.clone(),
+            })?;
+        Ok(Mnemonic::Fixed((Scoreboard::try_from(score)?, scale)))
+    } else {
+        Err(too_few_operand(mnemonic, 2, tokens))
     }
-    macro_rules! cmp {
-        ($x:path) => {
-            compare(
-                tokens.get(0),
-                tokens.get(1),
-                tokens.get(2),
-                tokens.get(3..),
-                |score1: Scoreboard, score2: Scoreboard, mnemonic: Mnemonic| {
-                    Mnemonic::Je((score1, Box::new(score2), Box::new(mnemonic)))
-                },
-                |score1: Scoreboard, int: i64, mnemonic: Mnemonic| {
-                    Mnemonic::Je((score1, Box::new(IntLiteral::from(int)), Box::new(mnemonic)))
-                },
-            )
-        };
+}
+
+fn long(mnemonic: &Token, tokens: &[Token]) -> Result<Mnemonic, MCAsmError> {
+    if let [score, ..] = tokens {
+        let register = Scoreboard::try_from(score)?;
+        // The words are named after the holder, which a selector or `*` can't be.
+        if register.scoreholder.starts_with(['@', '*']) {
+            return Err(MCAsmError::InvalidOperand {
+                expected: "a scoreboard held by a name or a fake player",
+                found: score.clone(),
+            });
+        }
+        Ok(Mnemonic::Long(register))
+    } else {
+        Err(too_few_operand(mnemonic, 1, tokens))
+    }
+}
+
+fn score_only(
+    mnemonic: &Token,
+    tokens: &[Token],
+    closure: fn(Scoreboard) -> Mnemonic,
+) -> Result<Mnemonic, MCAsmError> {
+    if let [s1, ..] = tokens {
+        Ok(closure(Scoreboard::try_from(s1)?))
+    } else {
+        Err(too_few_operand(mnemonic, 1, tokens))
+    }
+}
+
+fn expression(mnemonic: &Token, tokens: &[Token]) -> Result<Mnemonic, MCAsmError> {
+    if let [target, equal, expression @ ..] = tokens {
+        let target = Scoreboard::try_from(target)?;
+        if equal.text != "=" {
+            return Err(MCAsmError::InvalidOperand {
+                expected: "`=`",
+                found: equal.clone(),
+            });
+        }
+        Ok(Mnemonic::Let((
+            target,
+            Expression::parse(expression, equal)?,
+        )))
+    } else {
+        Err(too_few_operand(mnemonic, 3, tokens))
+    }
+}
+
+fn swap(mnemonic: &Token, tokens: &[Token]) -> Result<Mnemonic, MCAsmError> {
+    if let [s1, s2, ..] = tokens {
+        Ok(Mnemonic::Swp((
+            Scoreboard::try_from(s1)?,
+            Scoreboard::try_from(s2)?,
+        )))
+    } else {
+        Err(too_few_operand(mnemonic, 2, tokens))
+    }
+}
+
+fn release(mnemonic: &Token, tokens: &[Token]) -> Result<Mnemonic, MCAsmError> {
+    if let [s1, ..] = tokens {
+        Ok(Mnemonic::Rel(Scoreboard::try_from(s1)?))
+    } else {
+        Err(too_few_operand(mnemonic, 1, tokens))
+    }
+}
+
+pub fn parse_tokens(mnemonic: &Token, tokens: &[Token]) -> Result<Mnemonic, MCAsmError> {
+    match mnemonic.text.as_str() {
+        "DEF" => score_and_score_or_integer(mnemonic, tokens, Mnemonic::Def),
+        "MOV" => score_and_score_or_integer(mnemonic, tokens, Mnemonic::Mov),
+        "ADD" => score_and_score_or_integer(mnemonic, tokens, Mnemonic::Add),
+        "SUB" => score_and_score_or_integer(mnemonic, tokens, Mnemonic::Sub),
+        "MUL" => score_and_score_or_integer(mnemonic, tokens, Mnemonic::Mul),
+        "DIV" => score_and_score_or_integer(mnemonic, tokens, Mnemonic::Div),
+        "SUR" => score_and_score_or_integer(mnemonic, tokens, Mnemonic::Sur),
+        "LET" => expression(mnemonic, tokens),
+        "MIN" => score_and_score_or_integer(mnemonic, tokens, Mnemonic::Min),
+        "MAX" => score_and_score_or_integer(mnemonic, tokens, Mnemonic::Max),
+        "SWP" => swap(mnemonic, tokens),
+        "NEG" => score_only(mnemonic, tokens, Mnemonic::Neg),
+        "ABS" => score_only(mnemonic, tokens, Mnemonic::Abs),
+        "AND" => score_and_score_or_integer_only(mnemonic, tokens, Mnemonic::And),
+        "OR" => score_and_score_or_integer_only(mnemonic, tokens, Mnemonic::Or),
+        "XOR" => score_and_score_or_integer_only(mnemonic, tokens, Mnemonic::Xor),
+        "NOT" => score_only(mnemonic, tokens, Mnemonic::Not),
+        "SHL" => shift(mnemonic, tokens, Mnemonic::Shl),
+        "SHR" => shift(mnemonic, tokens, Mnemonic::Shr),
+        "NTS" => nbtstorage_to_score(mnemonic, tokens),
+        "STN" => score_to_nbtstorage(mnemonic, tokens),
+        "REL" => release(mnemonic, tokens),
+        "JE" => compare(mnemonic, tokens, score_or_integer, Mnemonic::Je),
+        "JNE" => compare(mnemonic, tokens, score_or_integer, Mnemonic::Jne),
+        "JG" => compare(mnemonic, tokens, score_or_integer, Mnemonic::Jg),
+        "JL" => compare(mnemonic, tokens, score_or_integer, Mnemonic::Jl),
+        "JGE" => compare(mnemonic, tokens, score_or_integer, Mnemonic::Jge),
+        "JLE" => compare(mnemonic, tokens, score_or_integer, Mnemonic::Jle),
+        "JIN" => compare(mnemonic, tokens, range, Mnemonic::Jin),
+        "JNL" => compare(mnemonic, tokens, score_or_integer, Mnemonic::Jnl),
+        "JNG" => compare(mnemonic, tokens, score_or_integer, Mnemonic::Jng),
+        "JNLE" => compare(mnemonic, tokens, score_or_integer, Mnemonic::Jnle),
+        "JNGE" => compare(mnemonic, tokens, score_or_integer, Mnemonic::Jnge),
+        "JNIN" => compare(mnemonic, tokens, range, Mnemonic::Jnin),
+        "IF" if tokens
+            .first()
+            .is_some_and(|kind| matches!(kind.text.as_str(), "ENTITY" | "BLOCK" | "DATA")) =>
+        {
+            modifier(mnemonic, tokens)
+        }
+        "IF" => condition(mnemonic, tokens),
+        "AS" | "AT" | "POSITIONED" | "IN" | "UNLESS" | "STORE" => modifier(mnemonic, tokens),
+        "JMP" => jump(mnemonic, tokens),
+        ".fixed" => fixed(mnemonic, tokens),
+        ".long" => long(mnemonic, tokens),
+        _ => Err(MCAsmError::UnknownMnemonic(mnemonic.clone())),
+    }
+}
+
+/// Splits a line into whitespace-separated tokens, keeping the byte offset of each.
+/// Spaces inside brackets, braces and quotes don't split, so `@a[tag=x, limit=1]` is a single token.
+/// A line leaving one of them open is split at every space, for the error to point at the right token.
+pub fn tokenize(index: usize, line: &str) -> Vec<Token> {
+    split(index, line, true).unwrap_or_else(|| split(index, line, false).unwrap_or_default())
+}
+
+fn split(index: usize, line: &str, grouping: bool) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for (offset, c) in line
+        .char_indices()
+        .chain(std::iter::once((line.len(), ' ')))
+    {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '[' | '{' => depth += 1,
+                ']' | '}' => depth -= 1,
+                _ => {}
+            },
+        }
+        let splits = c.is_whitespace()
+            && ((depth <= 0 && quote.is_none()) || !grouping || offset == line.len());
+        match (splits, start) {
+            (true, Some(begin)) => {
+                tokens.push(Token::new(
+                    &line[begin..offset],
+                    Span::new(index, begin, offset),
+                ));
+                start = None;
+            }
+            (false, None) => start = Some(offset),
+            _ => {}
+        }
+    }
+    (!grouping || (depth == 0 && quote.is_none())).then_some(tokens)
+}
+
+/// Parses the `index`th line of a source. The index is only used for the spans.
+pub fn parse_line(index: usize, line: &str) -> Result<Mnemonic, MCAsmError> {
+    let tokens = tokenize(index, line);
+    match tokens.split_first() {
+        Some((single, [])) if single.text.ends_with(':') => Ok(Mnemonic::Label(label(
+            &single.slice(0..single.text.len() - 1),
+        )?)),
+        // A declaration reads the line itself, so it can't be guarded.
+        Some((mnemonic, operands)) if mnemonic.text == ".objective" => {
+            objective(mnemonic, operands, line)
+        }
+        Some((mnemonic, operands)) => parse_tokens(mnemonic, operands),
+        None => Err(MCAsmError::EmptyLineGiven {
+            span: Span::new(index, 0, line.len()),
+        }),
     }
-    match mnemonic_kind {
-        "DEF" => score_source_mnemonic!(Mnemonic::Def),
-        "MOV" => score_source_mnemonic!(Mnemonic::Mov),
-        "ADD" => score_source_mnemonic!(Mnemonic::Add),
-        "SUB" => score_source_mnemonic!(Mnemonic::Sub),
-        "MUL" => score_source_mnemonic!(Mnemonic::Mul),
-        "DIV" => score_source_mnemonic!(Mnemonic::Div),
-        "SUR" => score_source_mnemonic!(Mnemonic::Sur),
-        "NTS" => nbtstorage_to_score((tokens.get(0), tokens.get(1), tokens.get(2), tokens.get(3))),
-        "STN" => score_to_nbtstorage((tokens.get(0), tokens.get(1), tokens.get(2), tokens.get(3))),
-        "REL" => release(tokens.get(0)),
-        "JE" => cmp!(Mnemonic::Je),
-        "JNE" => cmp!(Mnemonic::Jne),
-        "JG" => cmp!(Mnemonic::Jg),
-        "JL" => cmp!(Mnemonic::Jl),
-        _ => Err(MCAsmError::UnknownMnemonic),
-    }
-}
-
-pub fn parse_line(line: &str) -> Result<Mnemonic, MCAsmError> {
-    let splitten: Vec<&str> = line.split_whitespace().collect();
-    let mnemonic = match splitten.get(0) {
-        Some(s) => s,
-        None => return Err(MCAsmError::EmptyLineGiven),
-    };
-    parse_tokens(mnemonic, splitten.get(1..).unwrap_or(&[]))
 }
 
-pub fn parse(mcassembly: &str) -> Result<Vec<Mnemonic>, Vec<(usize, MCAsmError)>> {
+pub fn parse(mcassembly: &str) -> Result<Vec<Mnemonic>, Vec<MCAsmError>> {
     let mut mnemonics = Vec::new();
     let mut errors = Vec::new();
 
     for (index, line) in mcassembly
         .lines()
-        .map(|s| s.trim())
         .enumerate()
-        .filter(|(_, s)| !s.is_empty())
-        .filter(|(_, s)| !s.starts_with("//"))
+        .filter(|(_, s)| !s.trim().is_empty())
+        .filter(|(_, s)| !s.trim_start().starts_with("//"))
     {
-        match parse_line(line) {
+        match parse_line(index, line) {
             Ok(o) => mnemonics.push(o),
-            Err(e) => errors.push((index, e)),
+            Err(e) => errors.push(e),
         }
     }
 
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use crate::{optimise::optimise, types::*};
+
+/// A compiled `.mcfunction`, named by its resource location.
+#[derive(Clone, Debug)]
+pub struct Function {
+    pub name: String,
+    pub body: Vec<Qualified>,
+}
+
+impl Function {
+    /// Where the function lives inside a data pack, e.g. `data/mcasm/function/main/loop.mcfunction`.
+    pub fn path(&self) -> String {
+        let (namespace, path) = self
+            .name
+            .split_once(':')
+            .unwrap_or(("minecraft", &self.name));
+        format!("data/{}/function/{}.mcfunction", namespace, path)
+    }
+    pub fn serialise(&self) -> String {
+        self.body.iter().cloned().into_single_string()
+    }
+}
+
+/// A run of mnemonics which can only be entered from its head.
+pub struct BasicBlock<'a> {
+    /// `None` for the entry block.
+    pub label: Option<&'a Token>,
+    pub mnemonics: Vec<&'a Mnemonic>,
+    /// The block run after this one when nothing jumped away.
+    pub fallthrough: Option<&'a Token>,
+}
+
+impl<'a> BasicBlock<'a> {
+    /// Labels of every block control can reach from this one.
+    pub fn successors(&self) -> Vec<&'a Token> {
+        self.mnemonics
+            .iter()
+            .filter_map(|mnemonic| mnemonic.jump_target())
+            .chain(self.fallthrough)
+            .collect()
+    }
+    fn to_function(&self, codegen: &mut Codegen) -> Result<Function, Vec<MCAsmError>> {
+        let mut body = Vec::new();
+        let mut errors = Vec::new();
+        for mnemonic in &self.mnemonics {
+            match mnemonic.to_qualified_in(codegen) {
+                Ok(o) => body.extend(o),
+                Err(e) => errors.push(e),
+            }
+        }
+        if let Some(next) = self.fallthrough {
+            body.push(Qualified::from(format!(
+                "function {}",
+                codegen.function(&next.text)
+            )));
+        }
+        if errors.is_empty() {
+            Ok(Function {
+                name: match self.label {
+                    Some(label) => codegen.function(&label.text),
+                    None => codegen.entry(),
+                },
+                body,
+            })
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// A program split at its labels. Every block becomes a function and jumps become calls.
+pub struct ControlFlowGraph<'a> {
+    pub blocks: Vec<BasicBlock<'a>>,
+}
+
+impl<'a> ControlFlowGraph<'a> {
+    pub fn new(mnemonics: &'a [Mnemonic]) -> Result<Self, Vec<MCAsmError>> {
+        let mut blocks = vec![BasicBlock {
+            label: None,
+            mnemonics: Vec::new(),
+            fallthrough: None,
+        }];
+        let mut errors = Vec::new();
+        let mut defined: HashMap<&str, &Token> = HashMap::new();
+
+        for mnemonic in mnemonics {
+            if let Mnemonic::Label(label) = mnemonic {
+                if defined.insert(&label.text, label).is_some() {
+                    errors.push(MCAsmError::DuplicateLabel(label.clone()));
+                }
+                let previous = blocks.last_mut().unwrap();
+                if !matches!(previous.mnemonics.last(), Some(Mnemonic::Jmp(_))) {
+                    previous.fallthrough = Some(label);
+                }
+                blocks.push(BasicBlock {
+                    label: Some(label),
+                    mnemonics: Vec::new(),
+                    fallthrough: None,
+                });
+            } else {
+                blocks.last_mut().unwrap().mnemonics.push(mnemonic);
+            }
+        }
+
+        errors.extend(
+            blocks
+                .iter()
+                .flat_map(|block| block.successors())
+                .filter(|target| !defined.contains_key(target.text.as_str()))
+                .map(|target| MCAsmError::UndefinedLabel(target.clone())),
+        );
+
+        if errors.is_empty() {
+            Ok(Self { blocks })
+        } else {
+            Err(errors)
+        }
+    }
+    pub fn to_functions(&self, codegen: &mut Codegen) -> Result<Vec<Function>, Vec<MCAsmError>> {
+        let mut functions = Vec::new();
+        let mut errors = Vec::new();
+        for block in &self.blocks {
+            match block.to_function(codegen) {
+                Ok(o) => functions.push(o),
+                Err(e) => errors.extend(e),
+            }
+        }
+        if errors.is_empty() {
+            Ok(functions)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Records the scales declared with `.fixed` and the registers declared with `.long`,
+/// which apply to the whole program wherever they are.
+fn declare(mnemonics: &[Mnemonic], codegen: &mut Codegen) -> Result<(), Vec<MCAsmError>> {
+    let mut errors = Vec::new();
+    for mnemonic in mnemonics {
+        match mnemonic {
+            Mnemonic::Fixed((score, scale)) => {
+                let key = Codegen::key(score);
+                match codegen.scales.get(&key) {
+                    Some(declared) if *declared != scale.value() => {
+                        errors.push(MCAsmError::ConflictingScale {
+                            scale: *declared,
+                            span: scale.span(),
+                        })
+                    }
+                    _ => {
+                        codegen.scales.insert(key, scale.value());
+                    }
+                }
+            }
+            Mnemonic::Long(score) => {
+                codegen.longs.insert(Codegen::key(score));
+            }
+            _ => {}
+        }
+    }
+    if let Some(score) = mnemonics.iter().find_map(|mnemonic| match mnemonic {
+        Mnemonic::Fixed((score, _)) if codegen.is_long(score) => Some(score),
+        _ => None,
+    }) {
+        errors.push(MCAsmError::UnsupportedLong {
+            operation: "`.fixed`",
+            span: score.span,
+        });
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Compiles a parsed program into one function per block, the entry block first,
+/// followed by the helper functions the blocks call. Each of them is optimised when asked to.
+pub fn compile(
+    mnemonics: &[Mnemonic],
+    codegen: &mut Codegen,
+) -> Result<Vec<Function>, Vec<MCAsmError>> {
+    declare(mnemonics, codegen)?;
+    let mut functions = ControlFlowGraph::new(mnemonics)?.to_functions(codegen)?;
+    functions.extend(codegen.helpers.iter().map(|(name, body)| Function {
+        name: name.clone(),
+        body: body.clone(),
+    }));
+    if codegen.optimise {
+        for function in &mut functions {
+            function.body = optimise(std::mem::take(&mut function.body), codegen);
+        }
+    }
+    Ok(functions)
+}
@@ -0,0 +1,86 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use crate::{program::Function, types::*};
+
+/// Things a program needs to exist before it runs, compiled into a function run on load.
+pub struct Setup {
+    objectives: BTreeMap<String, Objective>,
+    declared: HashSet<String>,
+    constants: BTreeSet<i32>,
+    /// Objective of the registers and constants, which has to match `Codegen::objective`.
+    objective: String,
+}
+
+impl Default for Setup {
+    fn default() -> Self {
+        Self::new("MC_ASM")
+    }
+}
+
+impl Setup {
+    pub fn new(objective: impl Into<String>) -> Self {
+        Self {
+            objectives: BTreeMap::new(),
+            declared: HashSet::new(),
+            constants: BTreeSet::new(),
+            objective: objective.into(),
+        }
+    }
+    /// Records every objective the mnemonics use, including the one of the registers.
+    /// Objectives nobody declared with `.objective` are created as `dummy`.
+    pub fn collect(&mut self, mnemonics: &[Mnemonic]) -> Result<(), Vec<MCAsmError>> {
+        let mut errors = Vec::new();
+        for mnemonic in mnemonics {
+            if let Mnemonic::Objective(objective) = mnemonic {
+                let name = objective.name.text.clone();
+                match self.objectives.get(&name) {
+                    Some(declared)
+                        if self.declared.contains(&name)
+                            && (declared.criteria != objective.criteria
+                                || declared.display_name != objective.display_name) =>
+                    {
+                        errors.push(MCAsmError::ConflictingObjective(objective.name.clone()))
+                    }
+                    _ => {
+                        self.objectives.insert(name.clone(), objective.clone());
+                        self.declared.insert(name);
+                    }
+                }
+            }
+        }
+        for objective in mnemonics
+            .iter()
+            .flat_map(|mnemonic| mnemonic.scoreboards())
+            .map(|score| &score.objective)
+            .chain([&self.objective])
+        {
+            self.objectives
+                .entry(objective.clone())
+                .or_insert_with(|| Objective::dummy(objective));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+    /// Records the constants a compilation put into the pool, so they are set on load.
+    pub fn collect_constants(&mut self, codegen: &Codegen) {
+        self.constants.extend(&codegen.constants);
+    }
+    pub fn commands(&self) -> Vec<Qualified> {
+        self.objectives
+            .values()
+            .map(|objective| Qualified::from(objective.add()))
+            .chain(self.constants.iter().map(|value| {
+                Qualified::from(Scoreboard::constant(*value, &self.objective).set(*value))
+            }))
+            .collect()
+    }
+    pub fn to_function(&self, name: impl Into<String>) -> Function {
+        Function {
+            name: name.into(),
+            body: self.commands(),
+        }
+    }
+}
@@ -0,0 +1,642 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{compile, optimise::Operation, types::*};
+
+mod interpreter;
+
+pub use interpreter::Interpreter;
+
+/// A value in NBT storage, as `STN` writes it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Nbt {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+}
+
+impl Nbt {
+    pub fn as_f64(self) -> f64 {
+        match self {
+            Self::Byte(value) => f64::from(value),
+            Self::Short(value) => f64::from(value),
+            Self::Int(value) => f64::from(value),
+            Self::Long(value) => value as f64,
+            Self::Float(value) => f64::from(value),
+            Self::Double(value) => value,
+        }
+    }
+    /// The value `execute store result storage` writes, converting the double like Java casts do.
+    fn stored(value: f64, storage_type: &StorageType) -> Self {
+        match storage_type {
+            StorageType::Byte => Self::Byte(value as i32 as i8),
+            StorageType::Short => Self::Short(value as i32 as i16),
+            StorageType::Int => Self::Int(value as i32),
+            StorageType::Long => Self::Long(value as i64),
+            StorageType::Float => Self::Float(value as f32),
+            StorageType::Double => Self::Double(value),
+        }
+    }
+}
+
+/// SNBT, e.g. `1b`, `3`, `4L` or `1.5f`.
+impl std::fmt::Display for Nbt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Byte(value) => write!(f, "{}b", value),
+            Self::Short(value) => write!(f, "{}s", value),
+            Self::Int(value) => write!(f, "{}", value),
+            Self::Long(value) => write!(f, "{}L", value),
+            Self::Float(value) => write!(f, "{}f", value),
+            Self::Double(value) => write!(f, "{}d", value),
+        }
+    }
+}
+
+/// Runs a program without the game, against scoreboards and NBT storage held in memory.
+/// Each mnemonic does what the commands it compiles into do in Java Edition:
+/// scores wrap around at 32 bits, `DIV` and `SUR` floor, dividing by zero leaves the score as it is,
+/// and `REL` removes the score, which conditions then treat as unset.
+pub struct Machine {
+    /// Scores by objective, then by holder. A holder without a score isn't there at all.
+    pub scores: BTreeMap<String, BTreeMap<String, i32>>,
+    /// Values by storage, e.g. `mcasm:data`, then by the path as written in the source.
+    pub storage: BTreeMap<String, BTreeMap<String, Nbt>>,
+    /// How many mnemonics run before giving up on a program which may never halt.
+    pub step_limit: usize,
+}
+
+impl Default for Machine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Machine {
+    pub const DEFAULT_STEP_LIMIT: usize = 1_000_000;
+
+    pub fn new() -> Self {
+        Self {
+            scores: BTreeMap::new(),
+            storage: BTreeMap::new(),
+            step_limit: Self::DEFAULT_STEP_LIMIT,
+        }
+    }
+    /// The value of the score, or `None` when it's unset.
+    pub fn score(&self, score: &Scoreboard) -> Option<i32> {
+        self.scores
+            .get(&score.objective)
+            .and_then(|holders| holders.get(&score.scoreholder))
+            .copied()
+    }
+    /// Runs the program from its first mnemonic until it falls off the end.
+    /// It's compiled first, so that it's rejected for the same errors, which also declares
+    /// the scales and long registers into the codegen.
+    pub fn run(
+        &mut self,
+        mnemonics: &[Mnemonic],
+        codegen: &mut Codegen,
+    ) -> Result<(), Vec<MCAsmError>> {
+        compile(mnemonics, codegen)?;
+        let labels = mnemonics
+            .iter()
+            .enumerate()
+            .filter_map(|(index, mnemonic)| match mnemonic {
+                Mnemonic::Label(label) => Some((label.text.as_str(), index)),
+                _ => None,
+            })
+            .collect::<HashMap<&str, usize>>();
+        let (mut counter, mut steps, mut jumped) = (0, 0, Span::default());
+        while let Some(mnemonic) = mnemonics.get(counter) {
+            steps += 1;
+            if steps > self.step_limit {
+                return Err(vec![MCAsmError::StepLimitExceeded {
+                    limit: self.step_limit,
+                    span: jumped,
+                }]);
+            }
+            counter = match self.execute(mnemonic, codegen).map_err(|e| vec![e])? {
+                Some(label) => {
+                    jumped = label.span;
+                    labels[label.text.as_str()]
+                }
+                None => counter + 1,
+            };
+        }
+        Ok(())
+    }
+    /// Every score and storage value, one per line, e.g. `mcasm::#a = 10` and `mcasm:data x = 1.5f`.
+    pub fn dump(&self) -> String {
+        let scores = self.scores.iter().flat_map(|(objective, holders)| {
+            holders
+                .iter()
+                .map(move |(holder, value)| format!("{}::{} = {}", objective, holder, value))
+        });
+        let storage = self.storage.iter().flat_map(|(storage, paths)| {
+            paths
+                .iter()
+                .map(move |(path, value)| format!("{} {} = {}", storage, path, value))
+        });
+        scores.chain(storage).collect::<Vec<String>>().join("\n")
+    }
+
+    /// Runs a mnemonic, returning the label it jumps to if it does.
+    fn execute<'a>(
+        &mut self,
+        mnemonic: &'a Mnemonic,
+        codegen: &mut Codegen,
+    ) -> Result<Option<&'a Token>, MCAsmError> {
+        if let Some((operation, target, source)) = Operation::of(mnemonic) {
+            self.operate(operation, target, source, codegen)?;
+            return Ok(None);
+        }
+        match mnemonic {
+            Mnemonic::Swp((score, other)) => {
+                let (value, other_value) = (self.read(score)?, self.read(other)?);
+                let (to, from) = (codegen.scale(score), codegen.scale(other));
+                self.set(score, rescale(other_value, to, from))?;
+                self.set(other, rescale(value, from, to))?;
+            }
+            Mnemonic::Neg(score) => {
+                let value = self.read(score)?;
+                self.set(score, value.wrapping_neg())?;
+            }
+            Mnemonic::Abs(score) => {
+                if let Some(value) = self.get(score)?
+                    && value < 0
+                {
+                    self.set(score, value.wrapping_neg())?;
+                }
+            }
+            Mnemonic::Not(score) => {
+                let value = self.read(score)?;
+                self.set(score, !value)?;
+            }
+            // `LET x = x` compiles into nothing, leaving an unset score unset.
+            Mnemonic::Let((score, Expression::Score(same)))
+                if Codegen::key(same) == Codegen::key(score) => {}
+            // Computed in the target's scale, or as an integer moved into it when it isn't in place.
+            Mnemonic::Let((score, expression)) => {
+                let value = if expression.in_place(score) {
+                    self.evaluate(expression, score, codegen)?
+                } else {
+                    let value = self.evaluate(expression, &codegen.register(0), codegen)?;
+                    let temporary = Source::Score {
+                        value,
+                        scale: 1,
+                        long: false,
+                    };
+                    combine(Operation::Mov, 0, score, temporary, codegen)?
+                };
+                self.store(score, value, codegen)?;
+            }
+            // A missing path fails `data get`, which stores 0.
+            Mnemonic::Nts((score, storage, path, magnif)) => {
+                let scale = f64::from(*magnif) * codegen.scale(score) as f64;
+                let value = self
+                    .storage
+                    .get(&storage.fullname())
+                    .and_then(|paths| paths.get(&path.path))
+                    .map_or(0, |nbt| (nbt.as_f64() * scale).floor() as i32);
+                self.set(score, value)?;
+            }
+            // The magnification is read back from the command, as the game parses it.
+            Mnemonic::Stn((storage, path, score, magnif)) => {
+                let magnif = (*magnif / codegen.scale(score) as f32)
+                    .to_string()
+                    .parse::<f64>()
+                    .unwrap_or(0.0);
+                let value = f64::from(self.get(score)?.unwrap_or(0)) * magnif;
+                self.storage
+                    .entry(storage.fullname())
+                    .or_default()
+                    .insert(path.path.clone(), Nbt::stored(value, &path.type_annotation));
+            }
+            Mnemonic::Rel(score) => self.reset(score)?,
+            Mnemonic::Jmp(label) => return Ok(Some(label)),
+            // Every clause is tested before the mnemonic runs, none of them changing a score.
+            Mnemonic::If((disjunction, guarded)) => {
+                let mut holds = false;
+                for conjunction in disjunction {
+                    let mut all = true;
+                    for clause in conjunction {
+                        all &= self.holds(
+                            clause.unless,
+                            clause.comparison,
+                            &clause.lhs,
+                            clause.rhs.as_operand(),
+                            codegen,
+                        )?;
+                    }
+                    holds |= all;
+                }
+                if holds {
+                    return self.execute(guarded, codegen);
+                }
+            }
+            Mnemonic::Execute((modifier, _)) => return Err(unsimulated(modifier)),
+            _ => {
+                if let Some((unless, comparison, lhs, rhs, guarded)) = mnemonic.comparison()
+                    && self.holds(unless, comparison, lhs, rhs.as_operand(), codegen)?
+                {
+                    return self.execute(guarded, codegen);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// An operation of a mnemonic taking a target and a source, converting between their scales
+    /// the way the compiled commands do.
+    fn operate(
+        &mut self,
+        operation: Operation,
+        target: &Scoreboard,
+        source: OperandRef,
+        codegen: &Codegen,
+    ) -> Result<(), MCAsmError> {
+        // Shifting by a literal of 0 or less compiles into nothing at all.
+        if let (Operation::Shl | Operation::Shr, OperandRef::Int(int)) = (operation, source)
+            && int.value() <= 0
+        {
+            return Ok(());
+        }
+        let value = self.long(OperandRef::Score(target), codegen)?;
+        let source = self.source(source, codegen)?;
+        let result = combine(operation, value, target, source, codegen)?;
+        self.store(target, result, codegen)
+    }
+
+    /// The value an expression computes into a score, in the score's scale.
+    /// Like `LET` lowers it, an operand more complex than a score or a literal is computed
+    /// as an integer first, as a register holds it.
+    fn evaluate(
+        &mut self,
+        expression: &Expression,
+        into: &Scoreboard,
+        codegen: &Codegen,
+    ) -> Result<i64, MCAsmError> {
+        match expression {
+            Expression::Negate(operand) => {
+                let value = self.evaluate(operand, into, codegen)? as i32;
+                Ok(value.wrapping_neg().into())
+            }
+            Expression::Binary((lhs, operator, rhs)) => {
+                let value = self.evaluate(lhs, into, codegen)?;
+                let source = match self.leaf(rhs, codegen)? {
+                    Some(source) => source,
+                    None => Source::Score {
+                        value: self.evaluate(rhs, &codegen.register(0), codegen)?,
+                        scale: 1,
+                        long: false,
+                    },
+                };
+                combine((*operator).into(), value, into, source, codegen)
+            }
+            _ => {
+                let source = self
+                    .leaf(expression, codegen)?
+                    .expect("a score or a literal");
+                combine(Operation::Mov, 0, into, source, codegen)
+            }
+        }
+    }
+    /// The source a score or a literal of an expression is, and `None` for anything more complex.
+    fn leaf<'a>(
+        &mut self,
+        expression: &'a Expression,
+        codegen: &Codegen,
+    ) -> Result<Option<Source<'a>>, MCAsmError> {
+        match expression {
+            Expression::Score(score) => self.source(OperandRef::Score(score), codegen).map(Some),
+            Expression::Int(int) => Ok(Some(Source::Int(int))),
+            Expression::Negate(_) | Expression::Binary(_) => Ok(None),
+        }
+    }
+    /// Reads a source, which sets an unset score to 0 first as operating with it does.
+    fn source<'a>(
+        &mut self,
+        source: OperandRef<'a>,
+        codegen: &Codegen,
+    ) -> Result<Source<'a>, MCAsmError> {
+        match source {
+            OperandRef::Score(score) => Ok(Source::Score {
+                value: self.long(source, codegen)?,
+                scale: codegen.scale(score),
+                long: codegen.is_long(score),
+            }),
+            OperandRef::Int(int) => Ok(Source::Int(int)),
+            OperandRef::Flt(float) => Ok(Source::Flt(float)),
+            OperandRef::Range(_) | OperandRef::ScoreRange(_) => {
+                unreachable!("ranges can't be a source")
+            }
+        }
+    }
+    /// Sets a score to a value, over both words of a long register.
+    fn store(
+        &mut self,
+        target: &Scoreboard,
+        value: i64,
+        codegen: &Codegen,
+    ) -> Result<(), MCAsmError> {
+        if !codegen.is_long(target) {
+            return self.set(target, value as i32);
+        }
+        let register = LongRegister::of(target);
+        self.set(&register.hi, (value >> 32) as i32)?;
+        self.set(&register.lo, value as i32)
+    }
+
+    /// The 64-bit value of a long register, a sign-extended score or a literal.
+    /// An unset register has both words set to 0 first, as an unset score has when operated on.
+    fn long(&mut self, source: OperandRef, codegen: &Codegen) -> Result<i64, MCAsmError> {
+        match source {
+            OperandRef::Score(score) if codegen.is_long(score) => {
+                let register = LongRegister::of(score);
+                let (hi, lo) = (self.read(&register.hi)?, self.read(&register.lo)?);
+                Ok(i64::from(hi) << 32 | i64::from(lo as u32))
+            }
+            OperandRef::Score(score) => self.read(score).map(i64::from),
+            OperandRef::Int(int) => Ok(int.value()),
+            _ => unreachable!("rejected when compiled"),
+        }
+    }
+
+    /// The 64-bit value of a side of a long comparison, `None` when a word of it is unset.
+    fn words(&self, side: OperandRef, codegen: &Codegen) -> Result<Option<i64>, MCAsmError> {
+        match side {
+            OperandRef::Score(score) if codegen.is_long(score) => {
+                let register = LongRegister::of(score);
+                let (hi, lo) = (self.get(&register.hi)?, self.get(&register.lo)?);
+                Ok(hi
+                    .zip(lo)
+                    .map(|(hi, lo)| i64::from(hi) << 32 | i64::from(lo as u32)))
+            }
+            OperandRef::Score(score) => Ok(self.get(score)?.map(i64::from)),
+            OperandRef::Int(int) => Ok(Some(int.value())),
+            _ => unreachable!("rejected when compiled"),
+        }
+    }
+    /// Whether a comparison holds. An unset score fails it before `unless` negates it,
+    /// as `execute if score` does.
+    fn holds(
+        &mut self,
+        unless: bool,
+        comparison: Comparison,
+        lhs: &Scoreboard,
+        rhs: OperandRef,
+        codegen: &Codegen,
+    ) -> Result<bool, MCAsmError> {
+        if codegen.is_long(lhs) || rhs.scoreboard().is_some_and(|s| codegen.is_long(s)) {
+            let lhs = self.words(OperandRef::Score(lhs), codegen)?;
+            let rhs = self.words(rhs, codegen)?;
+            let holds = lhs
+                .zip(rhs)
+                .is_some_and(|(lhs, rhs)| compare(comparison, lhs, rhs));
+            return Ok(holds != unless);
+        }
+        let Some(value) = self.get(lhs)? else {
+            return Ok(unless);
+        };
+        let value = i64::from(value);
+        let scale = codegen.scale(lhs);
+        let holds = match rhs {
+            OperandRef::Int(int) => compare(comparison, value, int.scaled(lhs, codegen)?.into()),
+            OperandRef::Score(score) => self.get(score)?.is_some_and(|other| {
+                compare_scaled(comparison, value, scale, other, codegen.scale(score))
+            }),
+            OperandRef::Range(range) => {
+                let bound = |bound: Option<i64>| {
+                    bound
+                        .map(|b| narrow_scaled(b, scale, range.span(), codegen))
+                        .transpose()
+                };
+                let (min, max) = (bound(range.min())?, bound(range.max())?);
+                min.is_none_or(|min| value >= min.into())
+                    && max.is_none_or(|max| value <= max.into())
+            }
+            OperandRef::ScoreRange(range) => {
+                let mut holds = true;
+                for (bound, comparison) in
+                    [(range.min(), Comparison::Ge), (range.max(), Comparison::Le)]
+                {
+                    holds &= match bound {
+                        Some(Bound::Int(int)) => {
+                            compare(comparison, value, int.scaled(lhs, codegen)?.into())
+                        }
+                        Some(Bound::Score(score)) => self.get(score)?.is_some_and(|other| {
+                            compare_scaled(comparison, value, scale, other, codegen.scale(score))
+                        }),
+                        None => true,
+                    };
+                }
+                holds
+            }
+            OperandRef::Flt(_) => unreachable!("fractions can't be compared with"),
+        };
+        Ok(holds != unless)
+    }
+
+    fn get(&self, score: &Scoreboard) -> Result<Option<i32>, MCAsmError> {
+        holder(score)?;
+        Ok(self.score(score))
+    }
+    /// The value of the score, which is set to 0 first when it's unset, as operating on it does.
+    fn read(&mut self, score: &Scoreboard) -> Result<i32, MCAsmError> {
+        holder(score)?;
+        Ok(*self
+            .scores
+            .entry(score.objective.clone())
+            .or_default()
+            .entry(score.scoreholder.clone())
+            .or_default())
+    }
+    /// Removes the score, or every score of the objective for `*`.
+    fn reset(&mut self, score: &Scoreboard) -> Result<(), MCAsmError> {
+        if score.scoreholder == "*" {
+            self.scores.remove(&score.objective);
+            return Ok(());
+        }
+        holder(score)?;
+        if let Some(holders) = self.scores.get_mut(&score.objective) {
+            holders.remove(&score.scoreholder);
+        }
+        Ok(())
+    }
+    fn set(&mut self, score: &Scoreboard, value: i32) -> Result<(), MCAsmError> {
+        holder(score)?;
+        self.scores
+            .entry(score.objective.clone())
+            .or_default()
+            .insert(score.scoreholder.clone(), value);
+        Ok(())
+    }
+}
+
+/// Rejects a holder standing for entities, which only a world has.
+fn holder(score: &Scoreboard) -> Result<(), MCAsmError> {
+    let what = match score.scoreholder.as_str() {
+        "*" => "`*`",
+        holder if holder.starts_with('@') => "a selector",
+        _ => return Ok(()),
+    };
+    Err(MCAsmError::Unsimulated {
+        what,
+        span: score.span,
+    })
+}
+
+fn unsimulated(modifier: &Modifier) -> MCAsmError {
+    let (what, span) = match modifier {
+        Modifier::As(selector) => ("`AS`", selector.span),
+        Modifier::At(selector) => ("`AT`", selector.span),
+        Modifier::Positioned([x, ..]) => ("`POSITIONED`", x.span),
+        Modifier::In(dimension) => ("`IN`", dimension.span),
+        Modifier::Entity { selector, .. } => ("`IF ENTITY`", selector.span),
+        Modifier::Block {
+            position: [x, ..], ..
+        } => ("`IF BLOCK`", x.span),
+        Modifier::Data { path, .. } => ("`IF DATA`", path.span),
+        Modifier::Store { .. } => ("`STORE`", Span::default()),
+    };
+    MCAsmError::Unsimulated { what, span }
+}
+
+/// A source of an operation as it's read: a literal, or the value of a score with its scale.
+/// A long register holds 64 bits, and any other score 32 of them.
+#[derive(Clone, Copy)]
+enum Source<'a> {
+    Int(&'a IntLiteral),
+    Flt(&'a FltLiteral),
+    Score { value: i64, scale: i64, long: bool },
+}
+
+/// The result of an operation on the value a target holds, converting between the scales
+/// the way the compiled commands do. An operation involving a long register is computed on 64 bits,
+/// of which a 32-bit target only takes the lower word.
+fn combine(
+    operation: Operation,
+    value: i64,
+    target: &Scoreboard,
+    source: Source,
+    codegen: &Codegen,
+) -> Result<i64, MCAsmError> {
+    if codegen.is_long(target) || matches!(source, Source::Score { long: true, .. }) {
+        let source = match source {
+            Source::Int(int) => int.value(),
+            Source::Score { value, .. } => value,
+            Source::Flt(_) => unreachable!("rejected when compiled"),
+        };
+        let result = match operation {
+            Operation::Mov => source,
+            Operation::Add => value.wrapping_add(source),
+            Operation::Sub => value.wrapping_sub(source),
+            Operation::Mul => value.wrapping_mul(source),
+            _ => unreachable!("rejected when compiled"),
+        };
+        return Ok(if codegen.is_long(target) {
+            result
+        } else {
+            i64::from(result as i32)
+        });
+    }
+    let value = value as i32;
+    let scale = codegen.scale(target);
+    let result = match (operation, source) {
+        (Operation::And | Operation::Or | Operation::Xor, _)
+        | (Operation::Shl | Operation::Shr, _) => {
+            operation.apply(value, unscaled(source, codegen)?)
+        }
+        (Operation::Mul, Source::Flt(float)) => Some(rescale(
+            value,
+            i64::from(float.scaled(target, codegen)?),
+            scale,
+        )),
+        (Operation::Div, Source::Flt(float)) => Some(rescale(
+            value,
+            scale,
+            i64::from(float.scaled(target, codegen)?),
+        )),
+        (Operation::Mul | Operation::Div, Source::Int(int)) => {
+            operation.apply(value, narrow(int.value(), int.span(), codegen)?)
+        }
+        (
+            Operation::Mul,
+            Source::Score {
+                value: other,
+                scale: other_scale,
+                ..
+            },
+        ) => operation
+            .apply(value, other as i32)
+            .map(|product| rescale(product, 1, other_scale)),
+        (
+            Operation::Div,
+            Source::Score {
+                value: divisor,
+                scale: other_scale,
+                ..
+            },
+        ) => {
+            let value = rescale(value, other_scale, 1);
+            Some(operation.apply(value, divisor as i32).unwrap_or(value))
+        }
+        (_, Source::Int(int)) => operation.apply(value, int.scaled(target, codegen)?),
+        (_, Source::Flt(float)) => operation.apply(value, float.scaled(target, codegen)?),
+        (
+            _,
+            Source::Score {
+                value: other,
+                scale: other_scale,
+                ..
+            },
+        ) => operation.apply(value, rescale(other as i32, scale, other_scale)),
+    };
+    Ok(result.unwrap_or(value).into())
+}
+
+/// The value of a source read as an integer, like a register of the compiler holds it.
+fn unscaled(source: Source, codegen: &Codegen) -> Result<i32, MCAsmError> {
+    match source {
+        Source::Score { value, scale, .. } => Ok(rescale(value as i32, 1, scale)),
+        Source::Int(int) => narrow(int.value(), int.span(), codegen),
+        Source::Flt(float) => float.scaled(&codegen.register(0), codegen),
+    }
+}
+
+fn compare(comparison: Comparison, lhs: i64, rhs: i64) -> bool {
+    match comparison {
+        Comparison::Eq | Comparison::In => lhs == rhs,
+        Comparison::Lt => lhs < rhs,
+        Comparison::Le => lhs <= rhs,
+        Comparison::Gt => lhs > rhs,
+        Comparison::Ge => lhs >= rhs,
+    }
+}
+
+/// Compares the values of two scores, each being its stored value divided by its scale.
+fn compare_scaled(comparison: Comparison, lhs: i64, scale: i64, rhs: i32, rhs_scale: i64) -> bool {
+    compare(comparison, lhs * rhs_scale, i64::from(rhs) * scale)
+}
+
+/// Multiplies the value by `numerator / denominator` like `Scoreboard::scale_by`,
+/// reduced first, wrapping around on the multiplication and flooring on the division.
+fn rescale(value: i32, numerator: i64, denominator: i64) -> i32 {
+    let (mut a, mut b) = (numerator.abs(), denominator.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    let divisor = a.max(1);
+    let mut value = value;
+    for (operation, factor) in [
+        (Operation::Mul, numerator / divisor),
+        (Operation::Div, denominator / divisor),
+    ] {
+        if factor != 1 {
+            value = operation.apply(value, factor as i32).unwrap_or(value);
+        }
+    }
+    value
+}
@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+
+use super::{Machine, Nbt, compare};
+use crate::{Function, optimise::Operation, types::*};
+
+/// What runs after a command.
+enum Flow {
+    Next,
+    /// Runs the function, then the commands after this one.
+    Call(String),
+    /// Leaves the function, running another one in its place for `return run function`.
+    Return(Option<String>),
+}
+
+/// Where `execute store` puts the result of the command it runs.
+enum Store {
+    Score(Scoreboard),
+    Storage {
+        storage: String,
+        path: String,
+        storage_type: StorageType,
+        scale: f64,
+    },
+}
+
+/// Runs compiled functions as the game reads them, one command string at a time,
+/// against the scoreboards and storage of a `Machine`. Only the commands the compiler emits are known:
+/// `scoreboard players`, `execute if`/`unless score`, `execute store`, `data get storage`,
+/// `function` and `return run`.
+pub struct Interpreter {
+    /// Commands of each function by resource location, e.g. `mcasm:main/loop`.
+    pub functions: HashMap<String, Vec<String>>,
+}
+
+impl Interpreter {
+    pub fn new(functions: &[Function]) -> Self {
+        Self {
+            functions: functions
+                .iter()
+                .map(|function| {
+                    let body = function.body.iter().cloned().map(Qualified::serialise);
+                    (function.name.clone(), body.collect())
+                })
+                .collect(),
+        }
+    }
+    /// Runs the function and every function it calls, until it returns.
+    /// Calls are kept on a stack of their own, so that a long loop of tail calls doesn't overflow.
+    pub fn call(&self, location: &str, machine: &mut Machine) -> Result<(), MCAsmError> {
+        let mut frames = vec![(self.body(location)?, 0)];
+        let mut steps = 0;
+        while let Some((body, index)) = frames.last_mut() {
+            let Some(command) = body.get(*index) else {
+                frames.pop();
+                continue;
+            };
+            *index += 1;
+            steps += 1;
+            if steps > machine.step_limit {
+                return Err(MCAsmError::StepLimitExceeded {
+                    limit: machine.step_limit,
+                    span: Span::default(),
+                });
+            }
+            let words = command.split_whitespace().collect::<Vec<&str>>();
+            let (_, flow) = self.command(&words, command, machine)?;
+            match flow {
+                Flow::Next => {}
+                Flow::Call(location) => frames.push((self.body(&location)?, 0)),
+                Flow::Return(location) => {
+                    frames.pop();
+                    if let Some(location) = location {
+                        frames.push((self.body(&location)?, 0));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn body(&self, location: &str) -> Result<&[String], MCAsmError> {
+        self.functions
+            .get(location)
+            .map(Vec::as_slice)
+            .ok_or_else(|| MCAsmError::InvalidCommand(format!("function {}", location)))
+    }
+
+    /// Runs the words of a command, returning its result, or `None` when it fails.
+    fn command(
+        &self,
+        words: &[&str],
+        command: &str,
+        machine: &mut Machine,
+    ) -> Result<(Option<i32>, Flow), MCAsmError> {
+        let invalid = || MCAsmError::InvalidCommand(command.to_string());
+        let int = |word: &str| word.parse::<i32>().map_err(|_| invalid());
+        let result = match words {
+            ["scoreboard", "objectives", "add", ..] => Some(0),
+            ["scoreboard", "players", "set", holder, objective, value] => {
+                let value = int(value)?;
+                machine.set(&Scoreboard::new(*holder, *objective), value)?;
+                Some(value)
+            }
+            [
+                "scoreboard",
+                "players",
+                operation @ ("add" | "remove"),
+                holder,
+                objective,
+                amount,
+            ] => {
+                let amount = int(amount).and_then(|a| (a >= 0).then_some(a).ok_or_else(invalid))?;
+                let score = Scoreboard::new(*holder, *objective);
+                let value = machine.read(&score)?;
+                let value = if *operation == "add" {
+                    value.wrapping_add(amount)
+                } else {
+                    value.wrapping_sub(amount)
+                };
+                machine.set(&score, value)?;
+                Some(value)
+            }
+            ["scoreboard", "players", "reset", holder, objective] => {
+                machine.reset(&Scoreboard::new(*holder, *objective))?;
+                Some(1)
+            }
+            ["scoreboard", "players", "get", holder, objective] => {
+                machine.get(&Scoreboard::new(*holder, *objective))?
+            }
+            [
+                "scoreboard",
+                "players",
+                "operation",
+                holder,
+                objective,
+                operation,
+                source,
+                source_objective,
+            ] => {
+                let (target, source) = (
+                    Scoreboard::new(*holder, *objective),
+                    Scoreboard::new(*source, *source_objective),
+                );
+                let (value, other) = (machine.read(&target)?, machine.read(&source)?);
+                let operation = match *operation {
+                    "=" => Operation::Mov,
+                    "+=" => Operation::Add,
+                    "-=" => Operation::Sub,
+                    "*=" => Operation::Mul,
+                    "/=" => Operation::Div,
+                    "%=" => Operation::Sur,
+                    "<" => Operation::Min,
+                    ">" => Operation::Max,
+                    "><" => {
+                        machine.set(&target, other)?;
+                        machine.set(&source, value)?;
+                        return Ok((Some(other), Flow::Next));
+                    }
+                    _ => return Err(invalid()),
+                };
+                let value = operation.apply(value, other).unwrap_or(value);
+                machine.set(&target, value)?;
+                Some(value)
+            }
+            // A missing path fails.
+            ["data", "get", "storage", storage, path, scale @ ..] => {
+                let scale = match scale {
+                    [] => 1.0,
+                    [scale] => scale.parse::<f64>().map_err(|_| invalid())?,
+                    _ => return Err(invalid()),
+                };
+                machine
+                    .storage
+                    .get(*storage)
+                    .and_then(|paths| paths.get(*path))
+                    .map(|nbt| (nbt.as_f64() * scale).floor() as i32)
+            }
+            ["function", location] => return Ok((None, Flow::Call(location.to_string()))),
+            ["return", "run", rest @ ..] => {
+                let (result, flow) = self.command(rest, command, machine)?;
+                let location = match flow {
+                    Flow::Next | Flow::Return(None) => None,
+                    Flow::Call(location) | Flow::Return(Some(location)) => Some(location),
+                };
+                return Ok((result, Flow::Return(location)));
+            }
+            ["execute", rest @ ..] => return self.execute(rest, command, machine),
+            _ => return Err(invalid()),
+        };
+        Ok((result, Flow::Next))
+    }
+
+    /// Runs the subcommands of `execute` in order. A failing condition stops it before anything
+    /// is stored, unless it's the last one, whose success is then the result.
+    fn execute(
+        &self,
+        mut words: &[&str],
+        command: &str,
+        machine: &mut Machine,
+    ) -> Result<(Option<i32>, Flow), MCAsmError> {
+        let invalid = || MCAsmError::InvalidCommand(command.to_string());
+        let mut stores = Vec::new();
+        let (result, flow) = loop {
+            match words {
+                ["run", rest @ ..] => break self.command(rest, command, machine)?,
+                [
+                    keyword @ ("if" | "unless"),
+                    "score",
+                    holder,
+                    objective,
+                    rest @ ..,
+                ] => {
+                    let lhs = machine.get(&Scoreboard::new(*holder, *objective))?;
+                    let (holds, rest) = match rest {
+                        ["matches", range, rest @ ..] => {
+                            let token = Token::new(*range, Span::default());
+                            let range = match IntLiteral::parse(range) {
+                                Some(value) => {
+                                    RangeLiteral::new(Some(value), Some(value), token.span)
+                                }
+                                None => RangeLiteral::try_from(&token)
+                                    .ok()
+                                    .flatten()
+                                    .ok_or_else(invalid)?,
+                            };
+                            let holds = lhs.is_some_and(|value| {
+                                let value = i64::from(value);
+                                range.min().is_none_or(|min| value >= min)
+                                    && range.max().is_none_or(|max| value <= max)
+                            });
+                            (holds, rest)
+                        }
+                        [operator, holder, objective, rest @ ..] => {
+                            let comparison = match *operator {
+                                "=" => Comparison::Eq,
+                                "<" => Comparison::Lt,
+                                "<=" => Comparison::Le,
+                                ">" => Comparison::Gt,
+                                ">=" => Comparison::Ge,
+                                _ => return Err(invalid()),
+                            };
+                            let rhs = machine.get(&Scoreboard::new(*holder, *objective))?;
+                            let holds = lhs.zip(rhs).is_some_and(|(lhs, rhs)| {
+                                compare(comparison, lhs.into(), rhs.into())
+                            });
+                            (holds, rest)
+                        }
+                        _ => return Err(invalid()),
+                    };
+                    let holds = holds != (*keyword == "unless");
+                    if rest.is_empty() {
+                        break (holds.then_some(1), Flow::Next);
+                    }
+                    if !holds {
+                        return Ok((None, Flow::Next));
+                    }
+                    words = rest;
+                }
+                [
+                    "store",
+                    kind @ ("result" | "success"),
+                    "score",
+                    holder,
+                    objective,
+                    rest @ ..,
+                ] => {
+                    stores.push((
+                        *kind == "success",
+                        Store::Score(Scoreboard::new(*holder, *objective)),
+                    ));
+                    words = rest;
+                }
+                [
+                    "store",
+                    kind @ ("result" | "success"),
+                    "storage",
+                    storage,
+                    path,
+                    storage_type,
+                    scale,
+                    rest @ ..,
+                ] => {
+                    stores.push((
+                        *kind == "success",
+                        Store::Storage {
+                            storage: storage.to_string(),
+                            path: path.to_string(),
+                            storage_type: StorageType::try_from(storage_type)
+                                .ok_or_else(invalid)?,
+                            scale: scale.parse().map_err(|_| invalid())?,
+                        },
+                    ));
+                    words = rest;
+                }
+                _ => return Err(invalid()),
+            }
+        };
+        if !stores.is_empty() && !matches!(flow, Flow::Next) {
+            return Err(invalid());
+        }
+        // A failed command stores 0 all the same.
+        for (success, store) in stores {
+            let value = if success {
+                i32::from(result.is_some())
+            } else {
+                result.unwrap_or(0)
+            };
+            match store {
+                Store::Score(score) => machine.set(&score, value)?,
+                Store::Storage {
+                    storage,
+                    path,
+                    storage_type,
+                    scale,
+                } => {
+                    let nbt = Nbt::stored(f64::from(value) * scale, &storage_type);
+                    machine
+                        .storage
+                        .entry(storage)
+                        .or_default()
+                        .insert(path, nbt);
+                }
+            }
+        }
+        Ok((result, flow))
+    }
+}
@@ -1,26 +1,30 @@
+mod bitwise;
+mod codegen;
+mod error;
+mod expression;
 mod literal;
+mod long;
+mod modifier;
 mod opecode;
 mod scoreboard;
+mod selector;
+mod span;
 mod storage;
 
-pub use literal::{FltLiteral, IntLiteral};
+pub use codegen::Codegen;
+pub use error::MCAsmError;
+pub use expression::{Expression, Operator};
+pub use literal::{Bound, FltLiteral, IntLiteral, RangeLiteral, ScoreRange};
+pub(crate) use literal::{narrow, narrow_scaled};
+pub use long::LongRegister;
+pub use modifier::{DataSource, Modifier, StoreTarget};
 pub use opecode::{
-    Command, Condition, IntoSingleString, Mnemonic, Qualified, Releasable, ScoreAddable,
-    ScoreAssignable, ScoreDividable, ScoreMultiplicatable, ScoreSubtractable, ScoreSurplusable,
+    AsOperand, Clause, Command, Comparing, Comparison, Condition, IntoSingleString, Mnemonic,
+    Operand, OperandRef, Qualified, Releasable, ScoreAddable, ScoreAssignable, ScoreCompareble,
+    ScoreDividable, ScoreMaximizable, ScoreMinimizable, ScoreMultiplicatable, ScoreSubtractable,
+    ScoreSurplusable,
 };
-pub use scoreboard::Scoreboard;
+pub use scoreboard::{Objective, Scoreboard};
+pub use selector::{ScoreHolder, Selector};
+pub use span::{Span, Token};
 pub use storage::{Path, Storage, StorageType};
-
-#[derive(Debug)]
-pub enum MCAsmError {
-    NarrowingConversion,
-    InvalidAssignment,
-    EmptyLineGiven,
-    TooFewOperand,
-    InvalidOperand,
-    UnknownMnemonic,
-    UnknownType,
-    InvalidScoreboard,
-    InvalidStorage,
-    CantImplicateAsUnsignedInteger,
-}
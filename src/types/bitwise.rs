@@ -0,0 +1,159 @@
+use super::literal::narrow;
+use crate::types::{long, *};
+
+/// Scratch scores for the commands bitwise operations are lowered into, e.g. `#bitwise_lhs MC_ASM`.
+/// They're named rather than allocated, as the helper functions are shared by every call.
+fn scratches(codegen: &Codegen) -> impl Fn(&str) -> Scoreboard + use<> {
+    let objective = codegen.objective.clone();
+    move |name| Scoreboard::new(format!("#bitwise_{}", name), objective.clone())
+}
+
+fn unsupported(mnemonic: &'static str, span: Span) -> MCAsmError {
+    MCAsmError::UnsupportedLong {
+        operation: mnemonic,
+        span,
+    }
+}
+
+/// Calls the function when the condition holds, or always without one.
+fn call(location: &str, conditions: Vec<Condition>) -> Qualified {
+    let command = Command::from(format!("function {}", location));
+    if conditions.is_empty() {
+        Qualified::from(command)
+    } else {
+        Qualified::from((command, conditions))
+    }
+}
+
+fn at_least(min: i64) -> RangeLiteral {
+    RangeLiteral::new(Some(min), None, Span::default())
+}
+
+/// `AND`, `OR` or `XOR`, going through the 32 bits of both sides in a helper function.
+/// A bit of the result is set by how many of the two bits are, e.g. both of them for `AND`.
+pub fn logic(
+    mnemonic: &'static str,
+    target: &Scoreboard,
+    source: &dyn ScoreAssignable,
+    codegen: &mut Codegen,
+) -> Result<Vec<Qualified>, MCAsmError> {
+    if long::involves(target, source.as_operand(), codegen) {
+        return Err(unsupported(mnemonic, target.span));
+    }
+    let scratch = scratches(codegen);
+    // Masking the lowest bits is what floor modulo by a power of two does.
+    if let OperandRef::Int(int) = source.as_operand() {
+        let mask = narrow(int.value(), int.span(), codegen)?;
+        if mnemonic == "`AND`" && (0..1 << 30).contains(&mask) && (mask + 1).count_ones() == 1 {
+            let modulus = codegen.pooled(mask + 1);
+            return Ok(vec![Qualified::from(target.operate("%=", &modulus))]);
+        }
+    }
+    let (name, set) = match mnemonic {
+        "`AND`" => ("and", RangeLiteral::new(Some(2), Some(2), Span::default())),
+        "`OR`" => ("or", at_least(1)),
+        _ => ("xor", RangeLiteral::new(Some(1), Some(1), Span::default())),
+    };
+    let helper = codegen.helper(name, |codegen, location| {
+        let two = codegen.pooled(2);
+        let zero = RangeLiteral::new(Some(0), Some(0), Span::default());
+        vec![
+            Qualified::from(scratch("bits").operate("=", &scratch("lhs"))),
+            Qualified::from(scratch("bits").operate("%=", &two)),
+            Qualified::from(scratch("bit").operate("=", &scratch("rhs"))),
+            Qualified::from(scratch("bit").operate("%=", &two)),
+            Qualified::from(scratch("bits").operate("+=", &scratch("bit"))),
+            Qualified::from((
+                scratch("result").operate("+=", &scratch("weight")),
+                vec![scratch("bits").matches(false, &set)],
+            )),
+            Qualified::from(scratch("lhs").operate("/=", &two)),
+            Qualified::from(scratch("rhs").operate("/=", &two)),
+            // The weight wraps around to `i32::MIN` for the sign bit, and to 0 after it.
+            Qualified::from(scratch("weight").operate("+=", &scratch("weight"))),
+            call(location, vec![scratch("weight").matches(true, &zero)]),
+        ]
+    });
+    let mut res = vec![Qualified::from(scratch("lhs").operate("=", target))];
+    res.extend(source.assign(&scratch("rhs"), codegen)?);
+    res.extend([
+        Qualified::from(scratch("result").set(0)),
+        Qualified::from(scratch("weight").set(1)),
+        call(&helper, vec![]),
+        Qualified::from(target.operate("=", &scratch("result"))),
+    ]);
+    Ok(res)
+}
+
+/// `NOT`, which is `-1 - x` in two's complement.
+pub fn not(target: &Scoreboard, codegen: &Codegen) -> Result<Vec<Qualified>, MCAsmError> {
+    if codegen.is_long(target) {
+        return Err(unsupported("`NOT`", target.span));
+    }
+    let scratch = scratches(codegen);
+    Ok(vec![
+        Qualified::from(scratch("lhs").set(-1)),
+        Qualified::from(scratch("lhs").operate("-=", target)),
+        Qualified::from(target.operate("=", &scratch("lhs"))),
+    ])
+}
+
+/// `SHL` or `SHR`, multiplying or floor dividing by a power of two. `SHR` keeps the sign.
+/// A literal amount is a single power, while a score one halves or doubles in a helper function.
+pub fn shift(
+    mnemonic: &'static str,
+    target: &Scoreboard,
+    amount: &dyn ScoreAssignable,
+    codegen: &mut Codegen,
+) -> Result<Vec<Qualified>, MCAsmError> {
+    if long::involves(target, amount.as_operand(), codegen) {
+        return Err(unsupported(mnemonic, target.span));
+    }
+    let scratch = scratches(codegen);
+    let left = mnemonic == "`SHL`";
+    if let OperandRef::Int(int) = amount.as_operand() {
+        let power =
+            |codegen: &mut Codegen, exponent: i64| codegen.pooled((1u32 << exponent) as i32);
+        return Ok(match (left, int.value()) {
+            (_, ..=0) => vec![],
+            (true, 32..) => vec![Qualified::from(target.set(0))],
+            // `1 << 31` wraps around to `i32::MIN`, which multiplies the same modulo 2^32.
+            (true, exponent) => vec![Qualified::from(
+                target.operate("*=", &power(codegen, exponent)),
+            )],
+            (false, exponent @ ..=30) => {
+                vec![Qualified::from(
+                    target.operate("/=", &power(codegen, exponent)),
+                )]
+            }
+            // 2^31 doesn't fit into a score, so it's divided in two steps.
+            (false, _) => vec![
+                Qualified::from(scratch("lhs").operate("=", target)),
+                Qualified::from(scratch("lhs").operate("/=", &power(codegen, 30))),
+                Qualified::from(scratch("lhs").operate("/=", &power(codegen, 1))),
+                Qualified::from(target.operate("=", &scratch("lhs"))),
+            ],
+        });
+    }
+    let (name, operation) = if left { ("shl", "*=") } else { ("shr", "/=") };
+    let helper = codegen.helper(name, |codegen, location| {
+        let two = codegen.pooled(2);
+        vec![
+            Qualified::from(scratch("lhs").operate(operation, &two)),
+            Qualified::from(scratch("rhs").remove(1)),
+            call(location, vec![scratch("rhs").matches(false, &at_least(1))]),
+        ]
+    });
+    let mut res = vec![Qualified::from(scratch("lhs").operate("=", target))];
+    res.extend(amount.assign(&scratch("rhs"), codegen)?);
+    res.extend([
+        // Shifting 32 times or more leaves nothing to shift.
+        Qualified::from((
+            scratch("rhs").set(32),
+            vec![scratch("rhs").matches(false, &at_least(33))],
+        )),
+        call(&helper, vec![scratch("rhs").matches(false, &at_least(1))]),
+        Qualified::from(target.operate("=", &scratch("lhs"))),
+    ]);
+    Ok(res)
+}
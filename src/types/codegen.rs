@@ -0,0 +1,108 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use crate::types::{Qualified, Scoreboard};
+
+/// Settings and state shared by every mnemonic while a program is turned into commands.
+#[derive(Clone, Debug)]
+pub struct Codegen {
+    /// Namespace the generated functions live in.
+    pub namespace: String,
+    /// Path of the entry function. Blocks started by labels are placed under it.
+    pub name: String,
+    /// Objective of the registers and constants the compiler introduces, `MC_ASM` by default.
+    pub objective: String,
+    /// Whether literals are read from constants set once on load, instead of setting a register every time.
+    pub constant_pool: bool,
+    /// Whether a literal overflowing a score wraps around instead of being an error.
+    pub allow_wrapping: bool,
+    /// Whether the compiled functions go through the peephole optimiser.
+    /// Folding the mnemonics beforehand with `fold` is up to the caller, as it takes them by value.
+    pub optimise: bool,
+    /// Literals referenced from the constant pool so far.
+    pub constants: BTreeSet<i32>,
+    /// Fixed-point scales declared with `.fixed`, keyed by `objective::holder`.
+    pub scales: HashMap<String, i64>,
+    /// Scoreboards declared with `.long`, keyed by `objective::holder`.
+    pub longs: HashSet<String>,
+    /// Functions the lowered commands call, like the loop of `AND`, keyed by resource location.
+    pub helpers: BTreeMap<String, Vec<Qualified>>,
+    /// Registers allocated by the instructions being lowered, which are the ones numbered below it.
+    pub registers: usize,
+}
+
+impl Default for Codegen {
+    fn default() -> Self {
+        Self::new("mcasm", "main")
+    }
+}
+
+impl Codegen {
+    pub fn new(namespace: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            namespace: namespace.into(),
+            name: name.into(),
+            objective: "MC_ASM".to_string(),
+            constant_pool: false,
+            allow_wrapping: false,
+            optimise: false,
+            constants: BTreeSet::new(),
+            scales: HashMap::new(),
+            longs: HashSet::new(),
+            helpers: BTreeMap::new(),
+            registers: 0,
+        }
+    }
+    /// Resource location of the entry function, e.g. `mcasm:main`.
+    pub fn entry(&self) -> String {
+        format!("{}:{}", self.namespace, self.name)
+    }
+    /// The key of a scoreboard in `scales` and `longs`.
+    pub fn key(scoreboard: &Scoreboard) -> String {
+        format!("{}::{}", scoreboard.objective, scoreboard.scoreholder)
+    }
+    /// How many units of the score make 1, which is 1 unless declared with `.fixed`.
+    pub fn scale(&self, scoreboard: &Scoreboard) -> i64 {
+        self.scales
+            .get(&Self::key(scoreboard))
+            .copied()
+            .unwrap_or(1)
+    }
+    /// Whether the scoreboard is a 64-bit register declared with `.long`.
+    pub fn is_long(&self, scoreboard: &Scoreboard) -> bool {
+        self.longs.contains(&Self::key(scoreboard))
+    }
+    /// A constant in the pool, regardless of `constant_pool`, for factors the compiler introduces.
+    pub fn pooled(&mut self, value: i32) -> Scoreboard {
+        self.constants.insert(value);
+        Scoreboard::constant(value, &self.objective)
+    }
+    /// The register of the number, e.g. `#r0 MC_ASM`.
+    pub fn register(&self, index: usize) -> Scoreboard {
+        Scoreboard::new(format!("#r{}", index), self.objective.clone())
+    }
+    /// A register none of the instructions being lowered holds a value in.
+    /// It's live until the instruction allocating it is lowered, and reused by the ones after it.
+    pub fn allocate(&mut self) -> Scoreboard {
+        let register = self.register(self.registers);
+        self.registers += 1;
+        register
+    }
+    /// Resource location of a helper function under `mc_asm/`, e.g. `mcasm:main/mc_asm/and`.
+    /// Its body is given its own location, for it to loop by calling itself, the first time it's used.
+    pub fn helper(
+        &mut self,
+        name: &str,
+        body: impl FnOnce(&mut Self, &str) -> Vec<Qualified>,
+    ) -> String {
+        let location = self.function(&format!("mc_asm/{}", name));
+        if !self.helpers.contains_key(&location) {
+            let body = body(self, &location);
+            self.helpers.insert(location.clone(), body);
+        }
+        location
+    }
+    /// Resource location of the function a label is compiled into, e.g. `mcasm:main/loop`.
+    pub fn function(&self, label: &str) -> String {
+        format!("{}:{}/{}", self.namespace, self.name, label)
+    }
+}
@@ -0,0 +1,201 @@
+use crate::types::{Span, Token};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MCAsmError {
+    NarrowingConversion {
+        value: i64,
+        span: Span,
+    },
+    EmptyLineGiven {
+        span: Span,
+    },
+    InexactFraction {
+        value: f64,
+        scale: i64,
+        span: Span,
+    },
+    ConflictingScale {
+        scale: i64,
+        span: Span,
+    },
+    /// The magnification of `NTS` times the fixed-point scale of the score, which `data get` is given.
+    MagnificationOverflow {
+        magnification: u32,
+        scale: i64,
+        span: Span,
+    },
+    /// Two scales whose scores are compared through remainders too large for a 32-bit score.
+    IncomparableScales {
+        scales: (i64, i64),
+        span: Span,
+    },
+    UnsupportedLong {
+        operation: &'static str,
+        span: Span,
+    },
+    Unsimulated {
+        what: &'static str,
+        span: Span,
+    },
+    StepLimitExceeded {
+        limit: usize,
+        span: Span,
+    },
+    /// A command the interpreter doesn't know, which has no span as it's compiled rather than written.
+    InvalidCommand(String),
+    TooFewOperand {
+        mnemonic: Token,
+        expected: usize,
+        found: usize,
+    },
+    InvalidOperand {
+        expected: &'static str,
+        found: Token,
+    },
+    UnknownMnemonic(Token),
+    UnknownType(Token),
+    InvalidScoreboard(Token),
+    InvalidScoreHolder(Token),
+    InvalidSelector {
+        expected: &'static str,
+        found: Token,
+    },
+    InvalidStorage(Token),
+    CantImplicateAsUnsignedInteger(Token),
+    InvalidLabel(Token),
+    UndefinedLabel(Token),
+    DuplicateLabel(Token),
+    ConflictingObjective(Token),
+}
+
+impl MCAsmError {
+    /// The part of the source the error is pointing at.
+    pub fn span(&self) -> Span {
+        match self {
+            Self::NarrowingConversion { span, .. }
+            | Self::EmptyLineGiven { span }
+            | Self::InexactFraction { span, .. }
+            | Self::ConflictingScale { span, .. }
+            | Self::MagnificationOverflow { span, .. }
+            | Self::IncomparableScales { span, .. }
+            | Self::UnsupportedLong { span, .. }
+            | Self::Unsimulated { span, .. }
+            | Self::StepLimitExceeded { span, .. } => *span,
+            Self::InvalidCommand(_) => Span::default(),
+            Self::TooFewOperand { mnemonic, .. } => mnemonic.span,
+            Self::InvalidOperand { found, .. } | Self::InvalidSelector { found, .. } => found.span,
+            Self::UnknownMnemonic(token)
+            | Self::UnknownType(token)
+            | Self::InvalidScoreboard(token)
+            | Self::InvalidScoreHolder(token)
+            | Self::InvalidStorage(token)
+            | Self::CantImplicateAsUnsignedInteger(token)
+            | Self::InvalidLabel(token)
+            | Self::UndefinedLabel(token)
+            | Self::DuplicateLabel(token)
+            | Self::ConflictingObjective(token) => token.span,
+        }
+    }
+}
+
+impl std::fmt::Display for MCAsmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NarrowingConversion { value, .. } => {
+                write!(f, "`{}` doesn't fit into a 32-bit score", value)
+            }
+            Self::EmptyLineGiven { .. } => write!(f, "expected a mnemonic, found an empty line"),
+            Self::InexactFraction { value, scale, .. } => write!(
+                f,
+                "`{}` can't be represented with a fixed-point scale of {}, declare a finer one with `.fixed`",
+                value, scale
+            ),
+            Self::UnsupportedLong { operation, .. } => {
+                write!(f, "{} isn't supported with long registers", operation)
+            }
+            Self::Unsimulated { what, .. } => {
+                write!(f, "{} can't be simulated without a world", what)
+            }
+            Self::StepLimitExceeded { limit, .. } => write!(
+                f,
+                "the program didn't halt within {} steps, it may loop forever",
+                limit
+            ),
+            Self::InvalidCommand(command) => write!(f, "can't interpret `{}`", command),
+            Self::ConflictingScale { scale, .. } => {
+                write!(f, "the scale is already declared as {}", scale)
+            }
+            Self::MagnificationOverflow {
+                magnification,
+                scale,
+                ..
+            } => write!(
+                f,
+                "the magnification {} times the fixed-point scale of {} doesn't fit into an unsigned 32-bit integer",
+                magnification, scale
+            ),
+            Self::IncomparableScales { scales, .. } => write!(
+                f,
+                "scores of the fixed-point scales {} and {} can't be compared within 32 bits",
+                scales.0, scales.1
+            ),
+            Self::TooFewOperand {
+                mnemonic,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{}` takes {} operand{}, but {} {} given",
+                mnemonic,
+                expected,
+                if *expected == 1 { "" } else { "s" },
+                found,
+                if *found == 1 { "was" } else { "were" }
+            ),
+            Self::InvalidOperand { expected, found } => {
+                write!(f, "expected {}, found `{}`", expected, found)
+            }
+            Self::UnknownMnemonic(token) => write!(f, "unknown mnemonic `{}`", token),
+            Self::UnknownType(token) => write!(
+                f,
+                "unknown type `{}`, expected one of `byte`, `short`, `int`, `long`, `float` or `double`",
+                token
+            ),
+            Self::InvalidScoreboard(token) => {
+                write!(f, "expected `objective::holder`, found `{}`", token)
+            }
+            Self::InvalidScoreHolder(token) => write!(
+                f,
+                "expected a name, `#name`, `*` or a selector as a score holder, found `{}`",
+                token
+            ),
+            Self::InvalidSelector { expected, found } => {
+                write!(
+                    f,
+                    "expected {} in the selector, found `{}`",
+                    expected, found
+                )
+            }
+            Self::InvalidStorage(token) => {
+                write!(f, "expected `namespace:name`, found `{}`", token)
+            }
+            Self::CantImplicateAsUnsignedInteger(token) => {
+                write!(f, "expected an unsigned integer, found `{}`", token)
+            }
+            Self::InvalidLabel(token) => write!(
+                f,
+                "`{}` can't be a label, only `a-z`, `0-9`, `_`, `-`, `.` and `/` are allowed, not starting with `mc_asm/`",
+                token
+            ),
+            Self::UndefinedLabel(token) => write!(f, "cannot find label `{}`", token),
+            Self::DuplicateLabel(token) => write!(f, "label `{}` is defined twice", token),
+            Self::ConflictingObjective(token) => write!(
+                f,
+                "objective `{}` is declared again with another criteria or display name",
+                token
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MCAsmError {}
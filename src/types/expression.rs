@@ -0,0 +1,320 @@
+use crate::types::*;
+
+/// A binary operator of an expression, each lowered into the arithmetic mnemonic of it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Sur,
+}
+
+impl Operator {
+    fn parse(text: &str) -> Option<Self> {
+        match text {
+            "+" => Some(Self::Add),
+            "-" => Some(Self::Sub),
+            "*" => Some(Self::Mul),
+            "/" => Some(Self::Div),
+            "%" => Some(Self::Sur),
+            _ => None,
+        }
+    }
+    /// How tightly it binds. `*`, `/` and `%` bind tighter than `+` and `-`.
+    fn precedence(self) -> u8 {
+        match self {
+            Self::Add | Self::Sub => 1,
+            Self::Mul | Self::Div | Self::Sur => 2,
+        }
+    }
+    /// The mnemonic applying the operator to the target with the source.
+    fn mnemonic(self, target: Scoreboard, source: Operand) -> Mnemonic {
+        match self {
+            Self::Add => Mnemonic::Add((target, source)),
+            Self::Sub => Mnemonic::Sub((target, source)),
+            Self::Mul => Mnemonic::Mul((target, source)),
+            Self::Div => Mnemonic::Div((target, source)),
+            Self::Sur => Mnemonic::Sur((target, source)),
+        }
+    }
+}
+
+/// The right-hand side of `LET`, e.g. `(mcasm::#b + mcasm::#c) * 3`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expression {
+    Score(Scoreboard),
+    Int(IntLiteral),
+    /// `- <Expression>`
+    Negate(Box<Expression>),
+    Binary((Box<Expression>, Operator, Box<Expression>)),
+}
+
+impl std::fmt::Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Add => "+",
+                Self::Sub => "-",
+                Self::Mul => "*",
+                Self::Div => "/",
+                Self::Sur => "%",
+            }
+        )
+    }
+}
+
+/// Written as in the source, with only the parentheses the precedence needs.
+/// The operators are left-associative, so a right operand binding as loosely is parenthesised too.
+impl std::fmt::Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let operand = |expression: &Expression, parenthesised: bool| {
+            if parenthesised {
+                format!("({})", expression)
+            } else {
+                expression.to_string()
+            }
+        };
+        match self {
+            Self::Score(score) => write!(f, "{}", score),
+            Self::Int(int) => write!(f, "{}", int.value()),
+            Self::Negate(negated) => write!(
+                f,
+                "- {}",
+                operand(negated, matches!(**negated, Self::Binary(_)))
+            ),
+            Self::Binary((lhs, operator, rhs)) => {
+                let binding = |expression: &Expression| match expression {
+                    Self::Binary((_, inner, _)) => Some(inner.precedence()),
+                    _ => None,
+                };
+                let precedence = operator.precedence();
+                write!(
+                    f,
+                    "{} {} {}",
+                    operand(lhs, binding(lhs).is_some_and(|inner| inner < precedence)),
+                    operator,
+                    operand(rhs, binding(rhs).is_some_and(|inner| inner <= precedence))
+                )
+            }
+        }
+    }
+}
+
+/// The tokens of an expression with the parentheses stuck to an operand split off, e.g. `(mcasm::#b`.
+fn lex(tokens: &[Token]) -> Vec<Token> {
+    let mut lexed = Vec::new();
+    for token in tokens {
+        let opening = token.text.len() - token.text.trim_start_matches('(').len();
+        let closing = token.text.len() - token.text.trim_end_matches(')').len();
+        let closing = closing.min(token.text.len() - opening);
+        lexed.extend((0..opening).map(|i| token.slice(i..i + 1)));
+        if opening + closing < token.text.len() {
+            lexed.push(token.slice(opening..token.text.len() - closing));
+        }
+        lexed.extend((token.text.len() - closing..token.text.len()).map(|i| token.slice(i..i + 1)));
+    }
+    lexed
+}
+
+/// A recursive descent over the lexed tokens, climbing the precedence of the operators.
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    /// The token the expression ends after, pointed at when it ends too early.
+    last: &'a Token,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+    fn next(&mut self) -> Result<&Token, MCAsmError> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .ok_or_else(|| MCAsmError::InvalidOperand {
+                expected: "an operand after it",
+                found: self.tokens.last().unwrap_or(self.last).clone(),
+            })?;
+        self.position += 1;
+        Ok(token)
+    }
+    fn binary(&mut self, min_precedence: u8) -> Result<Expression, MCAsmError> {
+        let mut lhs = self.unary()?;
+        while let Some(operator) = self
+            .peek()
+            .and_then(|token| Operator::parse(&token.text))
+            .filter(|operator| operator.precedence() >= min_precedence)
+        {
+            self.position += 1;
+            let rhs = self.binary(operator.precedence() + 1)?;
+            lhs = Expression::Binary((Box::new(lhs), operator, Box::new(rhs)));
+        }
+        Ok(lhs)
+    }
+    fn unary(&mut self) -> Result<Expression, MCAsmError> {
+        let token = self.next()?.clone();
+        match token.text.as_str() {
+            "-" => Ok(Expression::Negate(Box::new(self.unary()?))),
+            "(" => {
+                let inner = self.binary(1)?;
+                match self.peek() {
+                    Some(closing) if closing.text == ")" => {
+                        self.position += 1;
+                        Ok(inner)
+                    }
+                    found => Err(MCAsmError::InvalidOperand {
+                        expected: "`)` closing this",
+                        found: found.cloned().unwrap_or(token),
+                    }),
+                }
+            }
+            _ => match IntLiteral::try_from(&token) {
+                Some(int) => Ok(Expression::Int(int)),
+                None => Scoreboard::try_from(&token)
+                    .map(Expression::Score)
+                    .map_err(|_| MCAsmError::InvalidOperand {
+                        expected: "`objective::holder`, an integer or `(`",
+                        found: token,
+                    }),
+            },
+        }
+    }
+}
+
+impl Expression {
+    /// Parses the tokens after `=`. Operators and operands have to be separated with spaces,
+    /// while parentheses may stick to the operands.
+    pub fn parse(tokens: &[Token], equal: &Token) -> Result<Self, MCAsmError> {
+        let lexed = lex(tokens);
+        let mut parser = Parser {
+            tokens: &lexed,
+            position: 0,
+            last: equal,
+        };
+        let expression = parser.binary(1)?;
+        match parser.peek() {
+            None => Ok(expression),
+            Some(found) => Err(MCAsmError::InvalidOperand {
+                expected: "an operator like `+`",
+                found: found.clone(),
+            }),
+        }
+    }
+    /// Every operand of the expression, from left to right.
+    pub fn operands(&self) -> Vec<OperandRef<'_>> {
+        match self {
+            Self::Score(score) => vec![OperandRef::Score(score)],
+            Self::Int(int) => vec![OperandRef::Int(int)],
+            Self::Negate(operand) => operand.operands(),
+            Self::Binary((lhs, _, rhs)) => {
+                let mut operands = lhs.operands();
+                operands.extend(rhs.operands());
+                operands
+            }
+        }
+    }
+    /// Whether `LET target = self` is computed in the target itself,
+    /// which it is unless the target is read after the leftmost operand.
+    pub fn in_place(&self, target: &Scoreboard) -> bool {
+        let key = Codegen::key(target);
+        let reads = self
+            .operands()
+            .into_iter()
+            .filter_map(OperandRef::scoreboard)
+            .filter(|score| Codegen::key(score) == key)
+            .count();
+        let leftmost = match self.operands().first() {
+            Some(OperandRef::Score(score)) => Codegen::key(score) == key,
+            _ => false,
+        };
+        reads == 0 || (reads == 1 && leftmost)
+    }
+    /// Lowers `LET target = self` into the arithmetic mnemonics.
+    /// The target is computed in place unless it's read after the leftmost operand,
+    /// and operands more complex than a score or a literal are computed in registers,
+    /// which stay allocated for the mnemonics to allocate theirs above them, and are freed with `REL` afterwards.
+    pub fn lower(&self, target: &Scoreboard, codegen: &mut Codegen) -> Vec<Mnemonic> {
+        let mut lowering = Lowering {
+            base: codegen.registers,
+            objective: codegen.objective.clone(),
+            depth: 0,
+            used: 0,
+            mnemonics: Vec::new(),
+        };
+        if self.in_place(target) {
+            lowering.evaluate(self, target);
+        } else {
+            let temporary = lowering.allocate();
+            lowering.evaluate(self, &temporary);
+            lowering
+                .mnemonics
+                .push(Mnemonic::Mov((target.clone(), Operand::Score(temporary))));
+        }
+        codegen.registers = lowering.base + lowering.used;
+        let mut mnemonics = lowering.mnemonics;
+        mnemonics.extend(
+            (lowering.base..codegen.registers).map(|index| Mnemonic::Rel(codegen.register(index))),
+        );
+        mnemonics
+    }
+}
+
+struct Lowering {
+    /// The first register free when the expression started being lowered.
+    base: usize,
+    objective: String,
+    /// Registers in use, which are the ones numbered below it from `base`.
+    depth: usize,
+    /// Registers used so far.
+    used: usize,
+    mnemonics: Vec<Mnemonic>,
+}
+
+impl Lowering {
+    fn allocate(&mut self) -> Scoreboard {
+        let score = Scoreboard::new(
+            format!("#r{}", self.base + self.depth),
+            self.objective.clone(),
+        );
+        self.depth += 1;
+        self.used = self.used.max(self.depth);
+        score
+    }
+    fn evaluate(&mut self, expression: &Expression, into: &Scoreboard) {
+        match expression {
+            Expression::Score(score) if Codegen::key(score) == Codegen::key(into) => {}
+            Expression::Score(score) => self
+                .mnemonics
+                .push(Mnemonic::Mov((into.clone(), Operand::Score(score.clone())))),
+            Expression::Int(int) => self
+                .mnemonics
+                .push(Mnemonic::Mov((into.clone(), Operand::Int(int.clone())))),
+            Expression::Negate(operand) => {
+                self.evaluate(operand, into);
+                self.mnemonics.push(Mnemonic::Neg(into.clone()));
+            }
+            Expression::Binary((lhs, operator, rhs)) => {
+                self.evaluate(lhs, into);
+                let mnemonic = match rhs.as_ref() {
+                    Expression::Score(score) => {
+                        operator.mnemonic(into.clone(), Operand::Score(score.clone()))
+                    }
+                    Expression::Int(int) => {
+                        operator.mnemonic(into.clone(), Operand::Int(int.clone()))
+                    }
+                    _ => {
+                        let temporary = self.allocate();
+                        self.evaluate(rhs, &temporary);
+                        self.depth -= 1;
+                        operator.mnemonic(into.clone(), Operand::Score(temporary))
+                    }
+                };
+                self.mnemonics.push(mnemonic);
+            }
+        }
+    }
+}
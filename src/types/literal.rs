@@ -1,5 +1,8 @@
 mod float;
 mod integer;
+mod range;
 
 pub use float::FltLiteral;
 pub use integer::IntLiteral;
+pub(crate) use integer::{narrow, narrow_scaled};
+pub use range::{Bound, RangeLiteral, ScoreRange};
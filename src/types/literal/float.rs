@@ -1,3 +1,151 @@
+use super::integer::{add_value, narrow};
+use crate::types::*;
+
+/// A number with a fraction, e.g. `1.5`, only usable with scoreboards declared with `.fixed`.
+#[derive(Clone, Debug, PartialEq)]
 pub struct FltLiteral {
     data: f64,
+    span: Span,
+}
+
+impl From<f64> for FltLiteral {
+    fn from(value: f64) -> Self {
+        Self {
+            data: value,
+            span: Span::default(),
+        }
+    }
+}
+
+impl FltLiteral {
+    pub fn new(data: f64, span: Span) -> Self {
+        Self { data, span }
+    }
+    pub fn value(&self) -> f64 {
+        self.data
+    }
+    pub fn span(&self) -> Span {
+        self.span
+    }
+    /// The literal in units of the scoreboard's fixed-point scale, which has to be exact.
+    pub(crate) fn scaled(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &Codegen,
+    ) -> Result<i32, MCAsmError> {
+        let scale = codegen.scale(scoreboard);
+        let value = self.data * scale as f64;
+        let rounded = value.round();
+        if (value - rounded).abs() > 1e-9 * rounded.abs().max(1.0) {
+            return Err(MCAsmError::InexactFraction {
+                value: self.data,
+                scale,
+                span: self.span,
+            });
+        }
+        narrow(rounded as i64, self.span, codegen)
+    }
+}
+
+impl AsOperand for FltLiteral {
+    fn as_operand(&self) -> OperandRef<'_> {
+        OperandRef::Flt(self)
+    }
+}
+
+impl ScoreAssignable for FltLiteral {
+    fn assign(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError> {
+        let value = self.scaled(scoreboard, codegen)?;
+        Ok(vec![Qualified::from(scoreboard.set(value))])
+    }
+}
+
+impl ScoreAddable for FltLiteral {
+    fn add(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError> {
+        let value = self.scaled(scoreboard, codegen)?;
+        Ok(add_value(scoreboard, value, codegen))
+    }
+}
+
+impl ScoreSubtractable for FltLiteral {
+    fn sub(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError> {
+        let value = self.scaled(scoreboard, codegen)?;
+        Ok(add_value(scoreboard, value.wrapping_neg(), codegen))
+    }
+}
+
+impl ScoreMultiplicatable for FltLiteral {
+    /// Multiplies by the literal as a fraction of the scale, e.g. `3 / 2` for `1.5` in any scale.
+    fn mul(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError> {
+        let scale = codegen.scale(scoreboard);
+        let value = self.scaled(scoreboard, codegen)?;
+        Ok(scoreboard.scale_by(value as i64, scale, codegen))
+    }
+}
+
+impl ScoreDividable for FltLiteral {
+    fn div(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError> {
+        let scale = codegen.scale(scoreboard);
+        let value = self.scaled(scoreboard, codegen)?;
+        Ok(scoreboard.scale_by(scale, value as i64, codegen))
+    }
+}
+
+impl ScoreSurplusable for FltLiteral {
+    fn sur(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError> {
+        let value = self.scaled(scoreboard, codegen)?;
+        let (mut res, literal) = IntLiteral::to_score(value, codegen);
+        res.push(Qualified::from(scoreboard.operate("%=", &literal)));
+        Ok(res)
+    }
+}
+
+impl ScoreMinimizable for FltLiteral {
+    fn min(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError> {
+        let value = self.scaled(scoreboard, codegen)?;
+        let (mut res, literal) = IntLiteral::to_score(value, codegen);
+        res.push(Qualified::from(scoreboard.operate("<", &literal)));
+        Ok(res)
+    }
+}
+
+impl ScoreMaximizable for FltLiteral {
+    fn max(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError> {
+        let value = self.scaled(scoreboard, codegen)?;
+        let (mut res, literal) = IntLiteral::to_score(value, codegen);
+        res.push(Qualified::from(scoreboard.operate(">", &literal)));
+        Ok(res)
+    }
 }
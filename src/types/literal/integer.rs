@@ -1,75 +1,273 @@
-use crate::types::{opecode::ScoreCompareble, scoreboard::LSC, *};
+use crate::types::*;
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct IntLiteral {
     data: i64,
+    span: Span,
+}
+
+impl IntLiteral {
+    pub fn new(data: i64, span: Span) -> Self {
+        Self { data, span }
+    }
+    pub fn try_from(from: &Token) -> Option<Self> {
+        Self::parse(&from.text).map(|value| Self::new(value, from.span))
+    }
+    /// Reads an integer written in decimal, `0x` hexadecimal or `0b` binary, each with an optional sign
+    /// and `_` between digits, or a character like `'A'` for its code point.
+    pub fn parse(text: &str) -> Option<i64> {
+        if let Some(quoted) = text
+            .strip_prefix('\'')
+            .and_then(|rest| rest.strip_suffix('\''))
+        {
+            return character(quoted).map(|c| i64::from(u32::from(c)));
+        }
+        let (negative, unsigned) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (radix, digits) = match unsigned.get(..2) {
+            Some("0x" | "0X") => (16, &unsigned[2..]),
+            Some("0b" | "0B") => (2, &unsigned[2..]),
+            _ => (10, unsigned),
+        };
+        if digits.starts_with('_') || digits.ends_with('_') {
+            return None;
+        }
+        let digits = digits.replace('_', "");
+        if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+            return None;
+        }
+        let magnitude = i128::from(u64::from_str_radix(&digits, radix).ok()?);
+        i64::try_from(if negative { -magnitude } else { magnitude }).ok()
+    }
+    pub fn value(&self) -> i64 {
+        self.data
+    }
+    pub fn span(&self) -> Span {
+        self.span
+    }
+    /// The literal in units of the scoreboard's fixed-point scale, narrowed into a score.
+    pub(crate) fn scaled(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &Codegen,
+    ) -> Result<i32, MCAsmError> {
+        narrow_scaled(self.data, codegen.scale(scoreboard), self.span, codegen)
+    }
+    /// A score holding the value, for operations which only take scores.
+    /// That's the value's constant in the pool when it's enabled, or a register set just before.
+    pub(crate) fn to_score(value: i32, codegen: &mut Codegen) -> (Vec<Qualified>, Scoreboard) {
+        if codegen.constant_pool {
+            (vec![], codegen.pooled(value))
+        } else {
+            let register = codegen.allocate();
+            (vec![Qualified::from(register.set(value))], register)
+        }
+    }
+}
+
+/// The character between the quotes of a character literal, which is either itself or an escape.
+fn character(quoted: &str) -> Option<char> {
+    let mut chars = quoted.chars();
+    let c = match chars.next()? {
+        '\\' => match chars.next()? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            c @ ('\\' | '\'' | '"') => c,
+            _ => return None,
+        },
+        '\'' => return None,
+        c => c,
+    };
+    chars.next().is_none().then_some(c)
+}
+
+/// Narrows a value written in the source into a score, which only holds 32 bits.
+/// Every literal goes through here before reaching a command. Overflowing is an error,
+/// unless wrapping around is allowed deliberately.
+pub(crate) fn narrow(value: i64, span: Span, codegen: &Codegen) -> Result<i32, MCAsmError> {
+    match i32::try_from(value) {
+        Ok(narrowed) => Ok(narrowed),
+        Err(_) if codegen.allow_wrapping => Ok(value as i32),
+        Err(_) => Err(MCAsmError::NarrowingConversion { value, span }),
+    }
+}
+
+/// Narrows a value written in the source into a score after multiplying it by a fixed-point scale.
+/// A product overflowing 64 bits is an error as well, instead of wrapping back into range.
+pub(crate) fn narrow_scaled(
+    value: i64,
+    scale: i64,
+    span: Span,
+    codegen: &Codegen,
+) -> Result<i32, MCAsmError> {
+    match value.checked_mul(scale) {
+        Some(scaled) => narrow(scaled, span, codegen),
+        None if codegen.allow_wrapping => Ok(value.wrapping_mul(scale) as i32),
+        None => Err(MCAsmError::NarrowingConversion { value, span }),
+    }
+}
+
+/// Adds a value to the score. `scoreboard players add` only takes a non-negative amount,
+/// so a negative one is removed instead, and `i32::MIN`, which has no opposite, is added as a score.
+pub(crate) fn add_value(
+    scoreboard: &Scoreboard,
+    value: i32,
+    codegen: &mut Codegen,
+) -> Vec<Qualified> {
+    match value.checked_neg() {
+        None => {
+            let (mut res, literal) = IntLiteral::to_score(value, codegen);
+            res.push(Qualified::from(scoreboard.operate("+=", &literal)));
+            res
+        }
+        Some(opposite) if value < 0 => vec![Qualified::from(scoreboard.remove(opposite))],
+        Some(_) => vec![Qualified::from(scoreboard.add(value))],
+    }
+}
+
+impl AsOperand for IntLiteral {
+    fn as_operand(&self) -> OperandRef<'_> {
+        OperandRef::Int(self)
+    }
 }
 
 impl From<i64> for IntLiteral {
     fn from(value: i64) -> Self {
-        Self { data: value }
+        Self {
+            data: value,
+            span: Span::default(),
+        }
     }
 }
 
 impl ScoreAssignable for IntLiteral {
-    fn assign(&self, scoreboard: &Scoreboard) -> Result<Vec<Qualified>, MCAsmError> {
-        if self.data < (i32::MAX as i64) {
-            Ok(vec![Qualified::from(scoreboard.set(self.data as i32))])
-        } else {
-            Err(MCAsmError::NarrowingConversion)
-        }
+    fn assign(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError> {
+        let value = self.scaled(scoreboard, codegen)?;
+        Ok(vec![Qualified::from(scoreboard.set(value))])
     }
 }
 
 impl ScoreAddable for IntLiteral {
-    fn add(&self, scoreboard: &Scoreboard) -> Result<Vec<Qualified>, MCAsmError> {
-        Ok(vec![Qualified::from(scoreboard.add(self.data as i32))])
+    fn add(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError> {
+        let value = self.scaled(scoreboard, codegen)?;
+        Ok(add_value(scoreboard, value, codegen))
     }
 }
 
 impl ScoreSubtractable for IntLiteral {
-    fn sub(&self, scoreboard: &Scoreboard) -> Result<Vec<Qualified>, MCAsmError> {
-        Ok(vec![Qualified::from(scoreboard.remove(self.data as i32))])
+    fn sub(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError> {
+        // Subtracting `i32::MIN` is the same as adding it, as the score wraps around.
+        let value = self.scaled(scoreboard, codegen)?;
+        Ok(add_value(scoreboard, value.wrapping_neg(), codegen))
     }
 }
 
 impl ScoreMultiplicatable for IntLiteral {
-    fn mul(&self, scoreboard: &Scoreboard) -> Result<Vec<Qualified>, MCAsmError> {
-        Ok(vec![
-            Qualified::from(LSC.set(self.data as i32)),
-            Qualified::from(scoreboard.operate("*=", &LSC)),
-        ])
+    fn mul(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError> {
+        let (mut res, literal) = Self::to_score(narrow(self.data, self.span, codegen)?, codegen);
+        res.push(Qualified::from(scoreboard.operate("*=", &literal)));
+        Ok(res)
     }
 }
 
 impl ScoreDividable for IntLiteral {
-    fn div(&self, scoreboard: &Scoreboard) -> Result<Vec<Qualified>, MCAsmError> {
-        Ok(vec![
-            Qualified::from(LSC.set(self.data as i32)),
-            Qualified::from(scoreboard.operate("/=", &LSC)),
-        ])
+    fn div(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError> {
+        let (mut res, literal) = Self::to_score(narrow(self.data, self.span, codegen)?, codegen);
+        res.push(Qualified::from(scoreboard.operate("/=", &literal)));
+        Ok(res)
     }
 }
 
 impl ScoreSurplusable for IntLiteral {
-    fn sur(&self, scoreboard: &Scoreboard) -> Result<Vec<Qualified>, MCAsmError> {
-        Ok(vec![
-            Qualified::from(LSC.set(self.data as i32)),
-            Qualified::from(scoreboard.operate("%=", &LSC)),
-        ])
+    fn sur(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError> {
+        let value = self.scaled(scoreboard, codegen)?;
+        let (mut res, literal) = Self::to_score(value, codegen);
+        res.push(Qualified::from(scoreboard.operate("%=", &literal)));
+        Ok(res)
+    }
+}
+
+impl ScoreMinimizable for IntLiteral {
+    fn min(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError> {
+        let value = self.scaled(scoreboard, codegen)?;
+        let (mut res, literal) = Self::to_score(value, codegen);
+        res.push(Qualified::from(scoreboard.operate("<", &literal)));
+        Ok(res)
+    }
+}
+
+impl ScoreMaximizable for IntLiteral {
+    fn max(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError> {
+        let value = self.scaled(scoreboard, codegen)?;
+        let (mut res, literal) = Self::to_score(value, codegen);
+        res.push(Qualified::from(scoreboard.operate(">", &literal)));
+        Ok(res)
     }
 }
 
 impl ScoreCompareble for IntLiteral {
+    /// Compares with `matches`, turning the comparison into the range of values satisfying it.
     fn cmp(
         &self,
         unless: bool,
-        comparison: &str,
+        comparison: Comparison,
         scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
     ) -> Result<(Vec<Qualified>, Condition), MCAsmError> {
-        Ok((
-            vec![Qualified::from(LSC.set(self.data as i32))],
-            scoreboard.compare(unless, comparison, &*LSC),
-        ))
+        let value = self.scaled(scoreboard, codegen)? as i64;
+        let (min, max) = match comparison {
+            Comparison::Eq | Comparison::In => (Some(value), Some(value)),
+            Comparison::Lt => (None, Some(value - 1)),
+            Comparison::Le => (None, Some(value)),
+            Comparison::Gt => (Some(value + 1), None),
+            Comparison::Ge => (Some(value), None),
+        };
+        // Below `i32::MIN` or above `i32::MAX`, which `matches` can't take, no score is.
+        if min.is_some_and(|min| min > i32::MAX as i64)
+            || max.is_some_and(|max| max < i32::MIN as i64)
+        {
+            return Ok((
+                vec![],
+                scoreboard.compare(unless, Comparison::Lt, scoreboard),
+            ));
+        }
+        let range = RangeLiteral::new(min, max, self.span);
+        Ok((vec![], scoreboard.matches(unless, &range)))
     }
 }
@@ -0,0 +1,204 @@
+use super::integer::narrow_scaled;
+use crate::types::*;
+
+/// A range of integers as written in `matches`, e.g. `1..10`, `..4` or `6..`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RangeLiteral {
+    min: Option<i64>,
+    max: Option<i64>,
+    span: Span,
+}
+
+impl RangeLiteral {
+    pub fn new(min: Option<i64>, max: Option<i64>, span: Span) -> Self {
+        Self { min, max, span }
+    }
+    pub fn min(&self) -> Option<i64> {
+        self.min
+    }
+    pub fn max(&self) -> Option<i64> {
+        self.max
+    }
+    pub fn span(&self) -> Span {
+        self.span
+    }
+    /// Parses `min..max` where either bound can be left out, but not both.
+    /// It's `None` unless both ends are integers or left out, and an error when `min` is above `max`.
+    pub fn try_from(from: &Token) -> Result<Option<Self>, MCAsmError> {
+        let Some((min, max)) = from.text.split_once("..") else {
+            return Ok(None);
+        };
+        let bound = |s: &str| {
+            if s.is_empty() {
+                Some(None)
+            } else {
+                IntLiteral::parse(s).map(Some)
+            }
+        };
+        match bound(min).zip(bound(max)) {
+            None | Some((None, None)) => Ok(None),
+            Some((Some(min), Some(max))) if min > max => Err(MCAsmError::InvalidOperand {
+                expected: "a range whose minimum isn't above its maximum",
+                found: from.clone(),
+            }),
+            Some((min, max)) => Ok(Some(Self::new(min, max, from.span))),
+        }
+    }
+}
+
+impl std::fmt::Display for RangeLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) if min == max => write!(f, "{}", min),
+            (min, max) => write!(
+                f,
+                "{}..{}",
+                min.map(|m| m.to_string()).unwrap_or_default(),
+                max.map(|m| m.to_string()).unwrap_or_default()
+            ),
+        }
+    }
+}
+
+impl AsOperand for RangeLiteral {
+    fn as_operand(&self) -> OperandRef<'_> {
+        OperandRef::Range(self)
+    }
+}
+
+impl ScoreCompareble for RangeLiteral {
+    /// A range is only ever tested for membership, so the comparison is ignored.
+    fn cmp(
+        &self,
+        unless: bool,
+        _comparison: Comparison,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<(Vec<Qualified>, Condition), MCAsmError> {
+        let scale = codegen.scale(scoreboard);
+        let bound = |bound: Option<i64>| {
+            bound
+                .map(|bound| narrow_scaled(bound, scale, self.span, codegen).map(i64::from))
+                .transpose()
+        };
+        let scaled = Self::new(bound(self.min)?, bound(self.max)?, self.span);
+        Ok((vec![], scoreboard.matches(unless, &scaled)))
+    }
+}
+
+/// One end of a `ScoreRange`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Bound {
+    Int(IntLiteral),
+    Score(Scoreboard),
+}
+
+impl Bound {
+    fn as_compareble(&self) -> &dyn ScoreCompareble {
+        match self {
+            Self::Int(int) => int,
+            Self::Score(score) => score,
+        }
+    }
+}
+
+/// A range with at least one end read from a score, e.g. `mcasm::#lo..mcasm::#hi` or `0..mcasm::#hi`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScoreRange {
+    min: Option<Bound>,
+    max: Option<Bound>,
+    span: Span,
+}
+
+impl ScoreRange {
+    pub fn new(min: Option<Bound>, max: Option<Bound>, span: Span) -> Self {
+        Self { min, max, span }
+    }
+    pub fn min(&self) -> Option<&Bound> {
+        self.min.as_ref()
+    }
+    pub fn max(&self) -> Option<&Bound> {
+        self.max.as_ref()
+    }
+    pub fn span(&self) -> Span {
+        self.span
+    }
+    pub fn scoreboards(&self) -> Vec<&Scoreboard> {
+        [&self.min, &self.max]
+            .into_iter()
+            .filter_map(|bound| match bound {
+                Some(Bound::Score(score)) => Some(score),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for ScoreRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bound = |bound: &Option<Bound>| match bound {
+            Some(Bound::Int(int)) => int.value().to_string(),
+            Some(Bound::Score(score)) => score.to_string(),
+            None => String::new(),
+        };
+        write!(f, "{}..{}", bound(&self.min), bound(&self.max))
+    }
+}
+
+impl AsOperand for ScoreRange {
+    fn as_operand(&self) -> OperandRef<'_> {
+        OperandRef::ScoreRange(self)
+    }
+}
+
+impl ScoreCompareble for ScoreRange {
+    /// Tests each end with `>=` and `<=`. When both ends are there and the test is negated,
+    /// whether the value is in range is stored into a register first.
+    fn cmp(
+        &self,
+        unless: bool,
+        _comparison: Comparison,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<(Vec<Qualified>, Condition), MCAsmError> {
+        match (&self.min, &self.max) {
+            (Some(min), Some(max)) => {
+                let (mut res, above) =
+                    min.as_compareble()
+                        .cmp(false, Comparison::Ge, scoreboard, codegen)?;
+                let (below_res, below) =
+                    max.as_compareble()
+                        .cmp(false, Comparison::Le, scoreboard, codegen)?;
+                res.extend(below_res);
+                if unless {
+                    // `execute store` stores nothing when a condition before the last one fails,
+                    // so the flag is cleared first.
+                    let flag = codegen.allocate();
+                    res.push(Qualified::from(flag.set(0)));
+                    res.push(Qualified::from((flag.set(1), vec![above, below])));
+                    Ok((
+                        res,
+                        flag.matches(false, &RangeLiteral::new(Some(0), Some(0), self.span)),
+                    ))
+                } else {
+                    Ok((res, above.and(&below)))
+                }
+            }
+            (Some(min), None) => {
+                min.as_compareble()
+                    .cmp(unless, Comparison::Ge, scoreboard, codegen)
+            }
+            (None, Some(max)) => {
+                max.as_compareble()
+                    .cmp(unless, Comparison::Le, scoreboard, codegen)
+            }
+            (None, None) => Ok((
+                vec![],
+                scoreboard.matches(
+                    unless,
+                    &RangeLiteral::new(Some(i32::MIN as i64), None, self.span),
+                ),
+            )),
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use crate::types::*;
+
+/// Registers for the commands long arithmetic is lowered into, indexed as `scratch[0]` and so on.
+fn scratches(count: usize, codegen: &mut Codegen) -> Vec<Scoreboard> {
+    (0..count).map(|_| codegen.allocate()).collect()
+}
+
+/// A 64-bit value spread over two scores of the same objective:
+/// `holder.hi` is the signed upper word and `holder.lo` is the lower one, read as unsigned.
+pub struct LongRegister {
+    pub hi: Scoreboard,
+    pub lo: Scoreboard,
+}
+
+impl LongRegister {
+    pub fn of(scoreboard: &Scoreboard) -> Self {
+        let word = |suffix: &str| Scoreboard {
+            scoreholder: format!("{}.{}", scoreboard.scoreholder, suffix),
+            objective: scoreboard.objective.clone(),
+            span: scoreboard.span,
+        };
+        Self {
+            hi: word("hi"),
+            lo: word("lo"),
+        }
+    }
+}
+
+fn unsupported(operation: &'static str, span: Span) -> MCAsmError {
+    MCAsmError::UnsupportedLong { operation, span }
+}
+
+/// Both words of a source as scores, with the commands they need beforehand.
+/// A 32-bit score is sign-extended into `scratch[hi]`, and a literal is read from the constant pool.
+fn words(
+    source: OperandRef,
+    scratch: &[Scoreboard],
+    hi: usize,
+    codegen: &mut Codegen,
+) -> Result<(Vec<Qualified>, Scoreboard, Scoreboard), MCAsmError> {
+    match source {
+        OperandRef::Score(score) if codegen.is_long(score) => {
+            let register = LongRegister::of(score);
+            Ok((vec![], register.hi, register.lo))
+        }
+        OperandRef::Score(score) => {
+            let negative = RangeLiteral::new(None, Some(-1), Span::default());
+            let res = vec![
+                Qualified::from(scratch[hi].set(0)),
+                Qualified::from((scratch[hi].set(-1), vec![score.matches(false, &negative)])),
+            ];
+            Ok((res, scratch[hi].clone(), score.clone()))
+        }
+        OperandRef::Int(int) => {
+            let value = int.value();
+            Ok((
+                vec![],
+                codegen.pooled((value >> 32) as i32),
+                codegen.pooled(value as i32),
+            ))
+        }
+        OperandRef::Flt(float) => Err(unsupported("a fraction", float.span())),
+        OperandRef::Range(range) => Err(unsupported("a range", range.span())),
+        OperandRef::ScoreRange(range) => Err(unsupported("a range", range.span())),
+    }
+}
+
+/// Copies a word with its sign bit flipped, so that comparing them as signed compares the words as unsigned.
+fn unsigned(
+    word: &Scoreboard,
+    scratch: &[Scoreboard],
+    into: usize,
+    codegen: &mut Codegen,
+) -> Vec<Qualified> {
+    let min = codegen.pooled(i32::MIN);
+    vec![
+        Qualified::from(scratch[into].operate("=", word)),
+        Qualified::from(scratch[into].operate("+=", &min)),
+    ]
+}
+
+/// The upper word of the unsigned product of two words, into `scratch[4]`, with `scratch[5..=11]` as work.
+fn multiply_high(
+    a: &Scoreboard,
+    b: &Scoreboard,
+    scratch: &[Scoreboard],
+    codegen: &mut Codegen,
+) -> Vec<Qualified> {
+    let half = codegen.pooled(65536);
+    let set =
+        |index: usize, source: &Scoreboard| Qualified::from(scratch[index].operate("=", source));
+    let op = |index: usize, operation: &str, source: &Scoreboard| {
+        Qualified::from(scratch[index].operate(operation, source))
+    };
+    let (result, a0, a1, b0, b1, mid, product, low) = (4, 5, 6, 7, 8, 9, 10, 11);
+    let mut res = vec![
+        // Halves of both words: a = a1 * 65536 + a0
+        set(a0, a),
+        op(a0, "%=", &half),
+        set(a1, a),
+        op(a1, "/=", &half),
+        op(a1, "%=", &half),
+        set(b0, b),
+        op(b0, "%=", &half),
+        set(b1, b),
+        op(b1, "/=", &half),
+        op(b1, "%=", &half),
+        // The carry into the upper word starts from the upper half of a0 * b0
+        set(mid, &scratch[a0]),
+        op(mid, "*=", &scratch[b0]),
+        op(mid, "/=", &half),
+        op(mid, "%=", &half),
+        set(result, &scratch[a1]),
+        op(result, "*=", &scratch[b1]),
+    ];
+    for (x, y) in [(a0, b1), (a1, b0)] {
+        res.extend([
+            set(product, &scratch[x]),
+            op(product, "*=", &scratch[y]),
+            set(low, &scratch[product]),
+            op(low, "%=", &half),
+            op(mid, "+=", &scratch[low]),
+            op(product, "/=", &half),
+            op(product, "%=", &half),
+            op(result, "+=", &scratch[product]),
+        ]);
+    }
+    res.extend([op(mid, "/=", &half), op(result, "+=", &scratch[mid])]);
+    res
+}
+
+/// The mnemonic of an operation long registers don't support.
+fn mnemonic(operation: &str) -> &'static str {
+    match operation {
+        "/=" => "`DIV`",
+        "%=" => "`SUR`",
+        "<" => "`MIN`",
+        ">" => "`MAX`",
+        "><" => "`SWP`",
+        "neg" => "`NEG`",
+        _ => "`ABS`",
+    }
+}
+
+fn operate(
+    operation: &'static str,
+    target: &Scoreboard,
+    source: OperandRef,
+    codegen: &mut Codegen,
+) -> Result<Vec<Qualified>, MCAsmError> {
+    let scratch = scratches(13, codegen);
+    if !codegen.is_long(target) {
+        // A 32-bit target takes the lower word, wrapping around like any other overflow.
+        // Adding 0 to the upper one sets it to 0 when it's unset, as the lower one is by the operation.
+        let (_, hi, lo) = words(source, &scratch, 0, codegen)?;
+        return match operation {
+            "=" | "+=" | "-=" | "*=" => Ok(vec![
+                Qualified::from(hi.add(0)),
+                Qualified::from(target.operate(operation, &lo)),
+            ]),
+            _ => Err(unsupported(mnemonic(operation), target.span)),
+        };
+    }
+    let register = LongRegister::of(target);
+    let (mut res, hi, lo) = words(source, &scratch, 0, codegen)?;
+    match operation {
+        "=" => res.extend([
+            Qualified::from(register.lo.operate("=", &lo)),
+            Qualified::from(register.hi.operate("=", &hi)),
+        ]),
+        // The lower word carried when it became smaller as unsigned.
+        "+=" => {
+            res.extend(unsigned(&register.lo, &scratch, 1, codegen));
+            res.push(Qualified::from(register.lo.operate("+=", &lo)));
+            res.extend(unsigned(&register.lo, &scratch, 2, codegen));
+            res.push(Qualified::from(register.hi.operate("+=", &hi)));
+            res.push(Qualified::from((
+                register.hi.add(1),
+                vec![scratch[1].compare(false, Comparison::Lt, &scratch[2])],
+            )));
+        }
+        // The lower word borrowed when it was smaller than the source's as unsigned.
+        "-=" => {
+            res.extend(unsigned(&register.lo, &scratch, 1, codegen));
+            res.extend(unsigned(&lo, &scratch, 2, codegen));
+            res.push(Qualified::from(register.lo.operate("-=", &lo)));
+            res.push(Qualified::from(register.hi.operate("-=", &hi)));
+            res.push(Qualified::from((
+                register.hi.remove(1),
+                vec![scratch[2].compare(false, Comparison::Lt, &scratch[1])],
+            )));
+        }
+        // (hi * 2^32 + lo) * (hi' * 2^32 + lo') keeps lo * lo' and the upper word of it,
+        // plus hi * lo' + lo * hi' in the upper word.
+        "*=" => {
+            res.extend([
+                Qualified::from(scratch[3].operate("=", &register.hi)),
+                Qualified::from(scratch[3].operate("*=", &lo)),
+                Qualified::from(scratch[12].operate("=", &register.lo)),
+                Qualified::from(scratch[12].operate("*=", &hi)),
+                Qualified::from(scratch[3].operate("+=", &scratch[12])),
+            ]);
+            res.extend(multiply_high(&register.lo, &lo, &scratch, codegen));
+            res.extend([
+                Qualified::from(scratch[3].operate("+=", &scratch[4])),
+                Qualified::from(register.lo.operate("*=", &lo)),
+                Qualified::from(register.hi.operate("=", &scratch[3])),
+            ]);
+        }
+        _ => return Err(unsupported(mnemonic(operation), target.span)),
+    }
+    Ok(res)
+}
+
+/// Lowers an arithmetic mnemonic touching a long register, leaving anything else to its operands.
+pub fn lower(
+    mnemonic: &Mnemonic,
+    codegen: &mut Codegen,
+) -> Option<Result<Vec<Qualified>, MCAsmError>> {
+    let (operation, target, source) = match mnemonic {
+        Mnemonic::Def((target, source)) | Mnemonic::Mov((target, source)) => {
+            ("=", target, source.as_operand())
+        }
+        Mnemonic::Add((target, source)) => ("+=", target, source.as_operand()),
+        Mnemonic::Sub((target, source)) => ("-=", target, source.as_operand()),
+        Mnemonic::Mul((target, source)) => ("*=", target, source.as_operand()),
+        Mnemonic::Div((target, source)) => ("/=", target, source.as_operand()),
+        Mnemonic::Sur((target, source)) => ("%=", target, source.as_operand()),
+        Mnemonic::Min((target, source)) => ("<", target, source.as_operand()),
+        Mnemonic::Max((target, source)) => (">", target, source.as_operand()),
+        Mnemonic::Swp((target, source)) => ("><", target, OperandRef::Score(source)),
+        Mnemonic::Neg(target) => ("neg", target, OperandRef::Score(target)),
+        Mnemonic::Abs(target) => ("abs", target, OperandRef::Score(target)),
+        _ => return None,
+    };
+    involves(target, source, codegen).then(|| operate(operation, target, source, codegen))
+}
+
+/// Whether either side is a long register.
+pub fn involves(lhs: &Scoreboard, rhs: OperandRef, codegen: &Codegen) -> bool {
+    codegen.is_long(lhs) || rhs.scoreboard().is_some_and(|score| codegen.is_long(score))
+}
+
+/// Compares two sides, either of which is a long register, into a flag register.
+/// The upper words decide unless they are equal, in which case the lower ones do as unsigned.
+/// Like any comparison, it fails for an unset word and leaves the words as they are.
+pub fn compare(
+    unless: bool,
+    comparison: Comparison,
+    lhs: &Scoreboard,
+    rhs: OperandRef,
+    codegen: &mut Codegen,
+) -> Result<(Vec<Qualified>, Condition), MCAsmError> {
+    let scratch = scratches(4, codegen);
+    let flag = codegen.allocate();
+    let (mut res, lhs_hi, lhs_lo) = words(OperandRef::Score(lhs), &scratch, 0, codegen)?;
+    let (commands, rhs_hi, rhs_lo) = words(rhs, &scratch, 3, codegen)?;
+    res.extend(commands);
+    let equal = rhs_hi.compare(false, Comparison::Eq, &lhs_hi);
+    match comparison {
+        // Both words are tested in one `execute`, which stores nothing when the first fails.
+        Comparison::Eq => res.extend([
+            Qualified::from(flag.set(0)),
+            Qualified::from((
+                flag.set(1),
+                vec![equal, rhs_lo.compare(false, Comparison::Eq, &lhs_lo)],
+            )),
+        ]),
+        Comparison::In => unreachable!("ranges are rejected by `words`"),
+        _ => {
+            let strict = match comparison {
+                Comparison::Lt | Comparison::Le => Comparison::Lt,
+                _ => Comparison::Gt,
+            };
+            // An unset lower word fails the comparison, so it's only copied when it's set,
+            // as copying it would set it to 0. Registers and constants are always set.
+            let set = RangeLiteral::new(Some(i32::MIN.into()), None, Span::default());
+            let mut guards = Vec::new();
+            for (word, into) in [(&lhs_lo, 1), (&rhs_lo, 2)] {
+                let copy = unsigned(word, &scratch, into, codegen);
+                if word.objective == codegen.objective {
+                    res.extend(copy);
+                    continue;
+                }
+                let guard = word.matches(false, &set);
+                for mut command in copy {
+                    command.push_condition(guard.clone());
+                    res.push(command);
+                }
+                guards.push(guard);
+            }
+            let less = rhs_hi.compare(false, strict, &lhs_hi);
+            if guards.is_empty() {
+                res.push(Qualified::from(less.store_success(&flag)));
+            } else {
+                res.extend([
+                    Qualified::from(flag.set(0)),
+                    Qualified::from((flag.set(1), [guards.clone(), vec![less]].concat())),
+                ]);
+            }
+            let lower = scratch[2].compare(false, comparison, &scratch[1]);
+            res.push(Qualified::from((
+                flag.set(1),
+                [vec![equal], guards, vec![lower]].concat(),
+            )));
+        }
+    }
+    let holds = RangeLiteral::new(Some(1), Some(1), Span::default());
+    Ok((res, flag.matches(unless, &holds)))
+}
@@ -0,0 +1,154 @@
+use crate::types::*;
+
+/// Where `IF DATA` looks for the path.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DataSource {
+    Block([Token; 3]),
+    Entity(Token),
+    Storage(Storage),
+}
+
+impl std::fmt::Display for DataSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Block([x, y, z]) => write!(f, "block {} {} {}", x, y, z),
+            Self::Entity(selector) => write!(f, "entity {}", selector),
+            Self::Storage(storage) => write!(f, "storage {}", storage.fullname()),
+        }
+    }
+}
+
+/// Where `STORE` puts the result of the command it wraps.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StoreTarget {
+    Score(Scoreboard),
+    Storage((Storage, Path, f32)),
+}
+
+impl std::fmt::Display for StoreTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Score(score) => write!(f, "score {} {}", score.scoreholder, score.objective),
+            Self::Storage((storage, path, magnif)) => write!(
+                f,
+                "storage {} {} {} {}",
+                storage.fullname(),
+                path.path,
+                path.type_annotation,
+                magnif
+            ),
+        }
+    }
+}
+
+/// A subcommand of `execute` changing how, or whether, the wrapped mnemonic runs.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Modifier {
+    /// `AS <Selector>`
+    As(Token),
+    /// `AT <Selector>`
+    At(Token),
+    /// `POSITIONED <X> <Y> <Z>`
+    Positioned([Token; 3]),
+    /// `IN <Dimension>`
+    In(Token),
+    /// `(IF | UNLESS) ENTITY <Selector>`
+    Entity { unless: bool, selector: Token },
+    /// `(IF | UNLESS) BLOCK <X> <Y> <Z> <Block>`
+    Block {
+        unless: bool,
+        position: [Token; 3],
+        block: Token,
+    },
+    /// `(IF | UNLESS) DATA (BLOCK <X> <Y> <Z> | ENTITY <Selector> | STORAGE <Storage>) <NBTPath>`
+    Data {
+        unless: bool,
+        source: DataSource,
+        path: Token,
+    },
+    /// `STORE (RESULT | SUCCESS) (<Scoreboard> | <Storage> <NBTPath & Datatype> <Magnification>)`
+    Store { success: bool, target: StoreTarget },
+}
+
+impl Modifier {
+    pub fn condition(&self) -> Condition {
+        let keyword = |unless: bool| if unless { "unless" } else { "if" };
+        Condition::from(match self {
+            Self::As(selector) => format!("as {}", selector),
+            Self::At(selector) => format!("at {}", selector),
+            Self::Positioned([x, y, z]) => format!("positioned {} {} {}", x, y, z),
+            Self::In(dimension) => format!("in {}", dimension),
+            Self::Entity { unless, selector } => {
+                format!("{} entity {}", keyword(*unless), selector)
+            }
+            Self::Block {
+                unless,
+                position: [x, y, z],
+                block,
+            } => format!("{} block {} {} {} {}", keyword(*unless), x, y, z, block),
+            Self::Data {
+                unless,
+                source,
+                path,
+            } => format!("{} data {} {}", keyword(*unless), source, path),
+            Self::Store { success, target } => format!(
+                "store {} {}",
+                if *success { "success" } else { "result" },
+                target
+            ),
+        })
+    }
+    /// The scoreboard the modifier writes, which needs its objective created.
+    pub fn scoreboard(&self) -> Option<&Scoreboard> {
+        match self {
+            Self::Store {
+                target: StoreTarget::Score(score),
+                ..
+            } => Some(score),
+            _ => None,
+        }
+    }
+}
+
+/// Written as in the source, e.g. `UNLESS DATA STORAGE mcasm:data flag`.
+impl std::fmt::Display for Modifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let keyword = |unless: bool| if unless { "UNLESS" } else { "IF" };
+        match self {
+            Self::As(selector) => write!(f, "AS {}", selector),
+            Self::At(selector) => write!(f, "AT {}", selector),
+            Self::Positioned([x, y, z]) => write!(f, "POSITIONED {} {} {}", x, y, z),
+            Self::In(dimension) => write!(f, "IN {}", dimension),
+            Self::Entity { unless, selector } => {
+                write!(f, "{} ENTITY {}", keyword(*unless), selector)
+            }
+            Self::Block {
+                unless,
+                position: [x, y, z],
+                block,
+            } => write!(f, "{} BLOCK {} {} {} {}", keyword(*unless), x, y, z, block),
+            Self::Data {
+                unless,
+                source,
+                path,
+            } => {
+                write!(f, "{} DATA ", keyword(*unless))?;
+                match source {
+                    DataSource::Block([x, y, z]) => write!(f, "BLOCK {} {} {}", x, y, z)?,
+                    DataSource::Entity(selector) => write!(f, "ENTITY {}", selector)?,
+                    DataSource::Storage(storage) => write!(f, "STORAGE {}", storage.fullname())?,
+                }
+                write!(f, " {}", path)
+            }
+            Self::Store { success, target } => {
+                write!(f, "STORE {} ", if *success { "SUCCESS" } else { "RESULT" })?;
+                match target {
+                    StoreTarget::Score(score) => write!(f, "{}", score),
+                    StoreTarget::Storage((storage, path, magnif)) => {
+                        write!(f, "{} {} {}", storage.fullname(), path, magnif)
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use super::Scoreboard;
-use crate::types::{storage::Storage, *};
+use crate::types::{bitwise, long, storage::Storage, *};
 
 #[derive(Clone, Debug)]
 pub struct Command {
@@ -12,9 +12,9 @@ impl From<String> for Command {
     }
 }
 
-impl Into<String> for Command {
-    fn into(self) -> String {
-        self.command
+impl From<Command> for String {
+    fn from(value: Command) -> Self {
+        value.command
     }
 }
 
@@ -29,6 +29,20 @@ impl From<String> for Condition {
     }
 }
 
+impl Condition {
+    /// A condition holding only when both of them hold.
+    pub fn and(&self, other: &Condition) -> Condition {
+        Condition::from(format!("{} {}", self.condition, other.condition))
+    }
+    /// Stores whether the condition holds into the score, 1 if it does and 0 otherwise.
+    pub fn store_success(&self, scoreboard: &Scoreboard) -> Command {
+        Command::from(format!(
+            "execute store success score {} {} {}",
+            scoreboard.scoreholder, scoreboard.objective, self.condition
+        ))
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Qualified {
     command: Command,
@@ -51,9 +65,29 @@ impl Qualified {
             self.command.command
         )
     }
+    pub fn command(&self) -> &str {
+        &self.command.command
+    }
+    /// Each condition guarding the command, e.g. `if score #a mcasm matches 1`.
+    pub fn conditions(&self) -> Vec<&str> {
+        self.conditions
+            .iter()
+            .flatten()
+            .map(|c| c.condition.as_str())
+            .collect()
+    }
+    /// Another command guarded by the same conditions.
+    pub fn replaced(&self, command: Command) -> Self {
+        Self {
+            command,
+            conditions: self.conditions.clone(),
+        }
+    }
+    /// Guards the command with the condition, put before the ones it already has
+    /// so that the outermost mnemonic comes first in `execute`.
     pub fn push_condition(&mut self, condition: Condition) {
         match &mut self.conditions {
-            Some(c) => c.push(condition),
+            Some(c) => c.insert(0, condition),
             None => self.conditions = Some(vec![condition]),
         }
     }
@@ -86,43 +120,381 @@ impl From<(Command, Vec<Condition>)> for Qualified {
     }
 }
 
-pub trait ScoreAssignable {
-    fn assign(&self, scoreboard: &Scoreboard) -> Result<Vec<Qualified>, MCAsmError>;
+/// What a boxed operand really is, for passes which need to look inside a mnemonic.
+#[derive(Clone, Copy)]
+pub enum OperandRef<'a> {
+    Score(&'a Scoreboard),
+    Int(&'a IntLiteral),
+    Flt(&'a FltLiteral),
+    Range(&'a RangeLiteral),
+    ScoreRange(&'a ScoreRange),
 }
 
-pub trait ScoreAddable {
-    fn add(&self, scoreboard: &Scoreboard) -> Result<Vec<Qualified>, MCAsmError>;
+impl<'a> OperandRef<'a> {
+    pub fn scoreboard(self) -> Option<&'a Scoreboard> {
+        match self {
+            Self::Score(score) => Some(score),
+            _ => None,
+        }
+    }
+    /// Every scoreboard the operand reads, including the bounds of a range.
+    pub fn scoreboards(self) -> Vec<&'a Scoreboard> {
+        match self {
+            Self::Score(score) => vec![score],
+            Self::ScoreRange(range) => range.scoreboards(),
+            _ => vec![],
+        }
+    }
 }
 
-pub trait ScoreSubtractable {
-    fn sub(&self, scoreboard: &Scoreboard) -> Result<Vec<Qualified>, MCAsmError>;
+pub trait AsOperand {
+    fn as_operand(&self) -> OperandRef<'_>;
 }
 
-pub trait ScoreMultiplicatable {
-    fn mul(&self, scoreboard: &Scoreboard) -> Result<Vec<Qualified>, MCAsmError>;
+/// An operand of a mnemonic as written, e.g. `mcasm::#a`, `3`, `1.5` or `1..10`.
+/// Which kinds a mnemonic takes is checked when it's parsed, and again when it's lowered,
+/// as a mnemonic can be built by hand too.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operand {
+    Score(Scoreboard),
+    Int(IntLiteral),
+    Float(FltLiteral),
+    Range(RangeLiteral),
+    ScoreRange(ScoreRange),
 }
 
-pub trait ScoreDividable {
-    fn div(&self, scoreboard: &Scoreboard) -> Result<Vec<Qualified>, MCAsmError>;
+/// A source every arithmetic mnemonic takes, which is a score or a number.
+pub(crate) trait Arithmetic:
+    ScoreAssignable
+    + ScoreAddable
+    + ScoreSubtractable
+    + ScoreMultiplicatable
+    + ScoreDividable
+    + ScoreSurplusable
+    + ScoreMinimizable
+    + ScoreMaximizable
+{
 }
 
-pub trait ScoreSurplusable {
-    fn sur(&self, scoreboard: &Scoreboard) -> Result<Vec<Qualified>, MCAsmError>;
+impl<T> Arithmetic for T where
+    T: ScoreAssignable
+        + ScoreAddable
+        + ScoreSubtractable
+        + ScoreMultiplicatable
+        + ScoreDividable
+        + ScoreSurplusable
+        + ScoreMinimizable
+        + ScoreMaximizable
+{
 }
 
-pub trait Releasable {
+impl Operand {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Score(score) => score.span,
+            Self::Int(int) => int.span(),
+            Self::Float(float) => float.span(),
+            Self::Range(range) => range.span(),
+            Self::ScoreRange(range) => range.span(),
+        }
+    }
+    fn invalid(&self, expected: &'static str) -> MCAsmError {
+        MCAsmError::InvalidOperand {
+            expected,
+            found: Token::new(self.to_string(), self.span()),
+        }
+    }
+    /// The source of an arithmetic mnemonic, which lowers it.
+    pub(crate) fn arithmetic(&self) -> Result<&dyn Arithmetic, MCAsmError> {
+        match self {
+            Self::Score(score) => Ok(score),
+            Self::Int(int) => Ok(int),
+            Self::Float(float) => Ok(float),
+            _ => Err(self.invalid("`objective::holder` or a number")),
+        }
+    }
+    /// The source of a bitwise mnemonic, which can't have a fraction.
+    pub(crate) fn integral(&self) -> Result<&dyn ScoreAssignable, MCAsmError> {
+        match self {
+            Self::Score(score) => Ok(score),
+            Self::Int(int) => Ok(int),
+            _ => Err(self.invalid("`objective::holder` or an integer")),
+        }
+    }
+    /// The right-hand side of a comparison, which lowers it.
+    pub(crate) fn compareble(&self) -> Result<&dyn ScoreCompareble, MCAsmError> {
+        match self {
+            Self::Score(score) => Ok(score),
+            Self::Int(int) => Ok(int),
+            Self::Range(range) => Ok(range),
+            Self::ScoreRange(range) => Ok(range),
+            Self::Float(_) => Err(self.invalid("`objective::holder`, an integer or a range")),
+        }
+    }
+}
+
+impl AsOperand for Operand {
+    fn as_operand(&self) -> OperandRef<'_> {
+        match self {
+            Self::Score(score) => OperandRef::Score(score),
+            Self::Int(int) => OperandRef::Int(int),
+            Self::Float(float) => OperandRef::Flt(float),
+            Self::Range(range) => OperandRef::Range(range),
+            Self::ScoreRange(range) => OperandRef::ScoreRange(range),
+        }
+    }
+}
+
+/// Written back as in the source. A range is always written with `..`, as `JIN` takes nothing else.
+impl std::fmt::Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Score(score) => write!(f, "{}", score),
+            Self::Int(int) => write!(f, "{}", int.value()),
+            Self::Float(float) => write!(f, "{:?}", float.value()),
+            Self::Range(range) => write!(
+                f,
+                "{}..{}",
+                range.min().map(|m| m.to_string()).unwrap_or_default(),
+                range.max().map(|m| m.to_string()).unwrap_or_default()
+            ),
+            Self::ScoreRange(range) => write!(f, "{}", range),
+        }
+    }
+}
+
+impl From<Scoreboard> for Operand {
+    fn from(value: Scoreboard) -> Self {
+        Self::Score(value)
+    }
+}
+
+impl From<IntLiteral> for Operand {
+    fn from(value: IntLiteral) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<FltLiteral> for Operand {
+    fn from(value: FltLiteral) -> Self {
+        Self::Float(value)
+    }
+}
+
+pub trait ScoreAssignable: AsOperand {
+    fn assign(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError>;
+}
+
+pub trait ScoreAddable: AsOperand {
+    fn add(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError>;
+}
+
+pub trait ScoreSubtractable: AsOperand {
+    fn sub(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError>;
+}
+
+pub trait ScoreMultiplicatable: AsOperand {
+    fn mul(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError>;
+}
+
+pub trait ScoreDividable: AsOperand {
+    fn div(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError>;
+}
+
+pub trait ScoreSurplusable: AsOperand {
+    fn sur(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError>;
+}
+
+pub trait ScoreMinimizable: AsOperand {
+    fn min(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError>;
+}
+
+pub trait ScoreMaximizable: AsOperand {
+    fn max(
+        &self,
+        scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError>;
+}
+
+pub trait Releasable: AsOperand {
     fn rel(&self) -> Vec<Qualified>;
 }
 
-pub trait ScoreCompareble {
+/// Operands of the conditional mnemonics: both sides of the comparison and the guarded mnemonic.
+pub type Comparing = (Scoreboard, Operand, Box<Mnemonic>);
+
+/// How the left-hand score of a conditional mnemonic is compared with its right-hand side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// Membership of a range. A single value is a range of itself.
+    In,
+}
+
+impl Comparison {
+    /// The operator of `execute if score`. `In` is spelled `matches` there, so it's `=` here.
+    pub fn operator(self) -> &'static str {
+        match self {
+            Self::Eq | Self::In => "=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+        }
+    }
+}
+
+pub trait ScoreCompareble: AsOperand {
     fn cmp(
         &self,
         unless: bool,
-        comparison: &str,
+        comparison: Comparison,
         scoreboard: &Scoreboard,
+        codegen: &mut Codegen,
     ) -> Result<(Vec<Qualified>, Condition), MCAsmError>;
 }
 
+/// One comparison of an `IF` condition, e.g. `mcasm::#a < 10`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clause {
+    pub unless: bool,
+    pub comparison: Comparison,
+    pub lhs: Scoreboard,
+    pub rhs: Operand,
+}
+
+impl Clause {
+    /// Commands to run beforehand and the condition holding when the clause does.
+    pub fn condition(
+        &self,
+        codegen: &mut Codegen,
+    ) -> Result<(Vec<Qualified>, Condition), MCAsmError> {
+        compare(self.unless, self.comparison, &self.lhs, &self.rhs, codegen)
+    }
+}
+
+/// Written as in the source, e.g. `mcasm::#a != 0`. A negated comparison is written as its converse,
+/// apart from a negated `in`, which is written as `!in`.
+impl std::fmt::Display for Clause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let operator = match (self.unless, self.comparison) {
+            (false, Comparison::Eq) => "==",
+            (true, Comparison::Eq) => "!=",
+            (false, Comparison::Lt) | (true, Comparison::Ge) => "<",
+            (false, Comparison::Le) | (true, Comparison::Gt) => "<=",
+            (false, Comparison::Gt) | (true, Comparison::Le) => ">",
+            (false, Comparison::Ge) | (true, Comparison::Lt) => ">=",
+            (false, Comparison::In) => "in",
+            (true, Comparison::In) => "!in",
+        };
+        write!(f, "{} {} {}", self.lhs, operator, self.rhs)
+    }
+}
+
+/// Compares through the right-hand side, unless a long register is involved.
+fn compare(
+    unless: bool,
+    comparison: Comparison,
+    lhs: &Scoreboard,
+    rhs: &Operand,
+    codegen: &mut Codegen,
+) -> Result<(Vec<Qualified>, Condition), MCAsmError> {
+    let compareble = rhs.compareble()?;
+    if long::involves(lhs, rhs.as_operand(), codegen) {
+        long::compare(unless, comparison, lhs, rhs.as_operand(), codegen)
+    } else {
+        compareble.cmp(unless, comparison, lhs, codegen)
+    }
+}
+
+/// Guards the commands of a mnemonic with the condition. Each of them tests it anew, so when one
+/// changes a score the condition tests, whether it holds is stored into the flag the rest test instead.
+/// The flag is allocated before the mnemonic is lowered, as the registers it allocates are released then.
+fn guard(condition: Condition, commands: Vec<Qualified>, flag: &Scoreboard) -> Vec<Qualified> {
+    let tested = condition
+        .condition
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .windows(2)
+        .map(|pair| Codegen::key(&Scoreboard::new(pair[0], pair[1])))
+        .collect::<Vec<String>>();
+    let changes = commands
+        .iter()
+        .take(commands.len().saturating_sub(1))
+        .flat_map(|q| written(q.command()))
+        .any(|key| tested.contains(&key));
+    let mut res = Vec::new();
+    let condition = if changes {
+        res.push(Qualified::from(flag.set(0)));
+        res.push(Qualified::from((flag.set(1), vec![condition])));
+        flag.matches(false, &RangeLiteral::new(Some(1), Some(1), Span::default()))
+    } else {
+        condition
+    };
+    res.extend(commands.into_iter().map(|mut q| {
+        q.push_condition(condition.clone());
+        q
+    }));
+    res
+}
+
+/// The scores a command writes, e.g. `#a mcasm` for `scoreboard players add #a mcasm 1`.
+/// The source of an operation is one of them, as an unset one is set to 0.
+fn written(command: &str) -> Vec<String> {
+    let words = command.split_whitespace().collect::<Vec<&str>>();
+    let key = |holder: &str, objective: &str| Codegen::key(&Scoreboard::new(holder, objective));
+    let mut keys = words
+        .windows(5)
+        .filter(|w| w[0] == "store" && w[2] == "score")
+        .map(|w| key(w[3], w[4]))
+        .collect::<Vec<String>>();
+    if let Some(i) = words.iter().position(|w| *w == "players")
+        && let Some(&[operation, holder, objective, ..]) = words.get(i + 1..)
+        && operation != "get"
+    {
+        keys.push(key(holder, objective));
+        if operation == "operation"
+            && let Some(&[_, source, source_objective]) = words.get(i + 4..i + 7)
+        {
+            keys.push(key(source, source_objective));
+        }
+    }
+    keys
+}
+
 pub trait IntoSingleString {
     fn into_single_string(self) -> String;
 }
@@ -138,37 +510,64 @@ where
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub enum Mnemonic {
     /// Define Mnemonic
     ///
     /// DEF <Scoreboard> <Source>
     ///
     /// Completedly same as MOV btw
-    Def((Scoreboard, Box<dyn ScoreAssignable>)),
+    Def((Scoreboard, Operand)),
     /// Move Mnemonic
     ///
     /// MOV <Scoreboard> <Source>
-    Mov((Scoreboard, Box<dyn ScoreAssignable>)),
+    Mov((Scoreboard, Operand)),
     /// Addition Mnemonic
     ///
     /// ADD <Scoreboard> <Source>
-    Add((Scoreboard, Box<dyn ScoreAddable>)),
+    Add((Scoreboard, Operand)),
     /// Subtraction Mnemonic
     ///
     /// SUB <Scoreboard> <Source>
-    Sub((Scoreboard, Box<dyn ScoreSubtractable>)),
+    Sub((Scoreboard, Operand)),
     /// Multiplication Mnemonic
     ///
     /// MUL <Scoreboard> <Source>
-    Mul((Scoreboard, Box<dyn ScoreMultiplicatable>)),
+    Mul((Scoreboard, Operand)),
     /// Division Mnemonic
     ///
     /// DIV <Scoreboard> <Source>
-    Div((Scoreboard, Box<dyn ScoreDividable>)),
+    Div((Scoreboard, Operand)),
     /// Surplus Mnemonic
     ///
     /// SUR <Scoreboard> <Source>
-    Sur((Scoreboard, Box<dyn ScoreSurplusable>)),
+    Sur((Scoreboard, Operand)),
+    /// Minimum Mnemonic
+    ///
+    /// MIN <Scoreboard> <Source>
+    Min((Scoreboard, Operand)),
+    /// Maximum Mnemonic
+    ///
+    /// MAX <Scoreboard> <Source>
+    Max((Scoreboard, Operand)),
+    /// Swap Mnemonic
+    ///
+    /// SWP <Scoreboard> <Scoreboard>
+    Swp((Scoreboard, Scoreboard)),
+    /// Negation Mnemonic
+    ///
+    /// NEG <Scoreboard>
+    Neg(Scoreboard),
+    /// Absolute Value Mnemonic
+    ///
+    /// ABS <Scoreboard>
+    Abs(Scoreboard),
+    /// Expression Statement
+    ///
+    /// LET <Scoreboard> = <Expression>
+    ///
+    /// Lowered into the arithmetic mnemonics, computing nested operands in temporaries
+    Let((Scoreboard, Expression)),
     /// NBT to Score Mnemonic
     ///
     /// NTS <Scoreboard> <StorageName> <NBTPath & Datatype(Unused)> <Magnification>
@@ -180,73 +579,480 @@ pub enum Mnemonic {
     /// Release Mnemoric
     ///
     /// REL <Scoreboard>
-    Rel(Box<dyn Releasable>),
+    Rel(Scoreboard),
     /// Jump if Equal
     ///
     /// JE <Source> <Source> <Mnemonic>
-    Je((Scoreboard, Box<dyn ScoreCompareble>, Box<Mnemonic>)),
+    Je(Comparing),
     /// Jump if Not Equal
     ///
     /// JNE <Source> <Source> <Mnemonic>
-    Jne((Scoreboard, Box<dyn ScoreCompareble>, Box<Mnemonic>)),
+    Jne(Comparing),
     /// Jump if Less Than
     ///
     /// JL <Source> <Source> <Mnemonic>
-    Jl((Scoreboard, Box<dyn ScoreCompareble>, Box<Mnemonic>)),
+    Jl(Comparing),
     /// Jump if Greater Than
     ///
     /// JG <Source> <Source> <Mnemonic>
-    Jg((Scoreboard, Box<dyn ScoreCompareble>, Box<Mnemonic>)),
+    Jg(Comparing),
+    /// Jump if Less Than or Equal
+    ///
+    /// JLE <Source> <Source> <Mnemonic>
+    Jle(Comparing),
+    /// Jump if Greater Than or Equal
+    ///
+    /// JGE <Source> <Source> <Mnemonic>
+    Jge(Comparing),
+    /// Jump if In Range
+    ///
+    /// JIN <Source> <Range> <Mnemonic>
+    Jin(Comparing),
+    /// Jump if Not Less Than
+    ///
+    /// JNL <Source> <Source> <Mnemonic>
+    Jnl(Comparing),
+    /// Jump if Not Greater Than
+    ///
+    /// JNG <Source> <Source> <Mnemonic>
+    Jng(Comparing),
+    /// Jump if Not Less Than or Equal
+    ///
+    /// JNLE <Source> <Source> <Mnemonic>
+    Jnle(Comparing),
+    /// Jump if Not Greater Than or Equal
+    ///
+    /// JNGE <Source> <Source> <Mnemonic>
+    Jnge(Comparing),
+    /// Jump if Not In Range
+    ///
+    /// JNIN <Source> <Range> <Mnemonic>
+    Jnin(Comparing),
+    /// Label Pseudo-mnemonic
+    ///
+    /// <Label>:
+    ///
+    /// Starts a new block, which is compiled into its own function
+    Label(Token),
+    /// Jump Mnemonic
+    ///
+    /// JMP <Label>
+    Jmp(Token),
+    /// Objective Directive
+    ///
+    /// .objective <Objective> <Criteria> <Display Name(Optional)>
+    ///
+    /// Chooses how an objective is created by the load function
+    Objective(Objective),
+    /// Compound Condition
+    ///
+    /// IF <Source> <Operator> <Source> [(&& | ||) <Source> <Operator> <Source>]... THEN <Mnemonic>
+    ///
+    /// Held as the clauses joined by `||`, each made of the clauses joined by `&&`
+    If((Vec<Vec<Clause>>, Box<Mnemonic>)),
+    /// Execute Modifier
+    ///
+    /// (AS | AT | POSITIONED | IN | IF | UNLESS | STORE) <Arguments> <Mnemonic>
+    ///
+    /// Runs the mnemonic under an `execute` subcommand, e.g. as every player
+    Execute((Modifier, Box<Mnemonic>)),
+    /// Fixed-point Directive
+    ///
+    /// .fixed <Scoreboard> <Scale>
+    ///
+    /// Declares the score holds its value multiplied by the scale, so that it can have a fraction
+    Fixed((Scoreboard, IntLiteral)),
+    /// Long Directive
+    ///
+    /// .long <Scoreboard>
+    ///
+    /// Declares the score is a 64-bit register, held in `holder.hi` and `holder.lo`
+    Long(Scoreboard),
+    /// Bitwise AND Mnemonic
+    ///
+    /// AND <Scoreboard> <Source>
+    And((Scoreboard, Operand)),
+    /// Bitwise OR Mnemonic
+    ///
+    /// OR <Scoreboard> <Source>
+    Or((Scoreboard, Operand)),
+    /// Bitwise XOR Mnemonic
+    ///
+    /// XOR <Scoreboard> <Source>
+    Xor((Scoreboard, Operand)),
+    /// Bitwise NOT Mnemonic
+    ///
+    /// NOT <Scoreboard>
+    Not(Scoreboard),
+    /// Shift Left Mnemonic
+    ///
+    /// SHL <Scoreboard> <Amount>
+    Shl((Scoreboard, Operand)),
+    /// Arithmetic Shift Right Mnemonic
+    ///
+    /// SHR <Scoreboard> <Amount>
+    Shr((Scoreboard, Operand)),
 }
 
 impl Mnemonic {
+    /// Splits a conditional mnemonic into whether it's negated, how it compares,
+    /// both sides of the comparison and the mnemonic it guards.
+    pub fn comparison(&self) -> Option<(bool, Comparison, &Scoreboard, &Operand, &Mnemonic)> {
+        let (unless, comparison, (lhs, rhs, mnemonic)) = match self {
+            Self::Je(c) => (false, Comparison::Eq, c),
+            Self::Jne(c) => (true, Comparison::Eq, c),
+            Self::Jl(c) => (false, Comparison::Lt, c),
+            Self::Jg(c) => (false, Comparison::Gt, c),
+            Self::Jle(c) => (false, Comparison::Le, c),
+            Self::Jge(c) => (false, Comparison::Ge, c),
+            Self::Jin(c) => (false, Comparison::In, c),
+            Self::Jnl(c) => (true, Comparison::Lt, c),
+            Self::Jng(c) => (true, Comparison::Gt, c),
+            Self::Jnle(c) => (true, Comparison::Le, c),
+            Self::Jnge(c) => (true, Comparison::Ge, c),
+            Self::Jnin(c) => (true, Comparison::In, c),
+            _ => return None,
+        };
+        Some((unless, comparison, lhs, rhs, mnemonic.as_ref()))
+    }
+    /// Every operand of the mnemonic, including the ones of the mnemonic it guards.
+    pub fn operands(&self) -> Vec<OperandRef<'_>> {
+        match self {
+            Self::Def((score, source)) | Self::Mov((score, source)) => {
+                vec![OperandRef::Score(score), source.as_operand()]
+            }
+            Self::Add((score, source)) => vec![OperandRef::Score(score), source.as_operand()],
+            Self::Sub((score, source)) => vec![OperandRef::Score(score), source.as_operand()],
+            Self::Mul((score, source)) => vec![OperandRef::Score(score), source.as_operand()],
+            Self::Div((score, source)) => vec![OperandRef::Score(score), source.as_operand()],
+            Self::Sur((score, source)) => vec![OperandRef::Score(score), source.as_operand()],
+            Self::Min((score, source)) => vec![OperandRef::Score(score), source.as_operand()],
+            Self::Max((score, source)) => vec![OperandRef::Score(score), source.as_operand()],
+            Self::Swp((score, other)) => vec![OperandRef::Score(score), OperandRef::Score(other)],
+            Self::Let((score, expression)) => {
+                let mut operands = vec![OperandRef::Score(score)];
+                operands.extend(expression.operands());
+                operands
+            }
+            Self::And((score, source))
+            | Self::Or((score, source))
+            | Self::Xor((score, source))
+            | Self::Shl((score, source))
+            | Self::Shr((score, source)) => vec![OperandRef::Score(score), source.as_operand()],
+            Self::Nts((score, ..))
+            | Self::Stn((_, _, score, _))
+            | Self::Fixed((score, _))
+            | Self::Long(score)
+            | Self::Not(score)
+            | Self::Neg(score)
+            | Self::Abs(score) => {
+                vec![OperandRef::Score(score)]
+            }
+            Self::Rel(score) => vec![OperandRef::Score(score)],
+            Self::Label(_) | Self::Jmp(_) | Self::Objective(_) => vec![],
+            Self::If((disjunction, mnemonic)) => {
+                let mut operands = disjunction
+                    .iter()
+                    .flatten()
+                    .flat_map(|clause| [OperandRef::Score(&clause.lhs), clause.rhs.as_operand()])
+                    .collect::<Vec<OperandRef>>();
+                operands.extend(mnemonic.operands());
+                operands
+            }
+            Self::Execute((modifier, mnemonic)) => {
+                let mut operands = modifier
+                    .scoreboard()
+                    .map(OperandRef::Score)
+                    .into_iter()
+                    .collect::<Vec<OperandRef>>();
+                operands.extend(mnemonic.operands());
+                operands
+            }
+            _ => match self.comparison() {
+                Some((_, _, lhs, rhs, mnemonic)) => {
+                    let mut operands = vec![OperandRef::Score(lhs), rhs.as_operand()];
+                    operands.extend(mnemonic.operands());
+                    operands
+                }
+                None => vec![],
+            },
+        }
+    }
+    /// Every scoreboard the mnemonic reads or writes.
+    pub fn scoreboards(&self) -> Vec<&Scoreboard> {
+        self.operands()
+            .into_iter()
+            .flat_map(OperandRef::scoreboards)
+            .collect()
+    }
+    /// The label this mnemonic jumps to, looking through conditions.
+    pub fn jump_target(&self) -> Option<&Token> {
+        match self {
+            Self::Jmp(label) => Some(label),
+            Self::If((_, mnemonic)) | Self::Execute((_, mnemonic)) => mnemonic.jump_target(),
+            _ => self
+                .comparison()
+                .and_then(|(_, _, _, _, mnemonic)| mnemonic.jump_target()),
+        }
+    }
     pub fn to_qualified(&self) -> Result<Vec<Qualified>, MCAsmError> {
-        let cmp = |unless: bool,
-                   comparison: &str,
-                   lhs: &Scoreboard,
-                   rhs: &Box<dyn ScoreCompareble + 'static>,
-                   mnemonic: &Box<Mnemonic>|
-         -> Result<Vec<Qualified>, MCAsmError> {
-            let cmp_result = rhs.cmp(unless, comparison, lhs)?;
-            let mut res = cmp_result.0;
-            res.extend(
-                mnemonic
-                    .to_qualified()?
+        self.to_qualified_in(&mut Codegen::default())
+    }
+    /// Lowers the mnemonic into commands. The registers it allocates are dead afterwards,
+    /// so the next mnemonic reuses them, while a mnemonic it guards gets ones of its own.
+    pub fn to_qualified_in(&self, codegen: &mut Codegen) -> Result<Vec<Qualified>, MCAsmError> {
+        let registers = codegen.registers;
+        let res = self.lower(codegen);
+        codegen.registers = registers;
+        res
+    }
+    fn lower(&self, codegen: &mut Codegen) -> Result<Vec<Qualified>, MCAsmError> {
+        if let Some(lowered) = long::lower(self, codegen) {
+            return lowered;
+        }
+        match self {
+            // Each kind of operand lowers the mnemonic in its own way.
+            Self::Def((score, source)) => source.arithmetic()?.assign(score, codegen),
+            Self::Mov((score, source)) => source.arithmetic()?.assign(score, codegen),
+
+            Self::Add((score, source)) => source.arithmetic()?.add(score, codegen),
+            Self::Sub((score, source)) => source.arithmetic()?.sub(score, codegen),
+            Self::Mul((score, source)) => source.arithmetic()?.mul(score, codegen),
+            Self::Div((score, source)) => source.arithmetic()?.div(score, codegen),
+            Self::Sur((score, source)) => source.arithmetic()?.sur(score, codegen),
+            Self::Min((score, source)) => source.arithmetic()?.min(score, codegen),
+            Self::Max((score, source)) => source.arithmetic()?.max(score, codegen),
+
+            Self::Let((score, expression)) => {
+                let mut res = Vec::new();
+                for mnemonic in expression.lower(score, codegen) {
+                    res.extend(mnemonic.to_qualified_in(codegen)?);
+                }
+                Ok(res)
+            }
+
+            // Each score is converted into the other's scale after swapping.
+            Self::Swp((score, other)) => {
+                let mut res = vec![Qualified::from(score.operate("><", other))];
+                let (to, from) = (codegen.scale(score), codegen.scale(other));
+                res.extend(score.scale_by(to, from, codegen));
+                res.extend(other.scale_by(from, to, codegen));
+                Ok(res)
+            }
+            Self::Neg(score) => {
+                let (mut res, minus_one) = IntLiteral::to_score(-1, codegen);
+                res.push(Qualified::from(score.operate("*=", &minus_one)));
+                Ok(res)
+            }
+            // `i32::MIN` has no opposite, so it stays as it is.
+            Self::Abs(score) => {
+                let (mut res, minus_one) = IntLiteral::to_score(-1, codegen);
+                let negative = RangeLiteral::new(None, Some(-1), Span::default());
+                res.push(Qualified::from((
+                    score.operate("*=", &minus_one),
+                    vec![score.matches(false, &negative)],
+                )));
+                Ok(res)
+            }
+
+            Self::And((score, source)) => {
+                bitwise::logic("`AND`", score, source.integral()?, codegen)
+            }
+            Self::Or((score, source)) => bitwise::logic("`OR`", score, source.integral()?, codegen),
+            Self::Xor((score, source)) => {
+                bitwise::logic("`XOR`", score, source.integral()?, codegen)
+            }
+            Self::Not(score) => bitwise::not(score, codegen),
+            Self::Shl((score, amount)) => {
+                bitwise::shift("`SHL`", score, amount.integral()?, codegen)
+            }
+            Self::Shr((score, amount)) => {
+                bitwise::shift("`SHR`", score, amount.integral()?, codegen)
+            }
+
+            // A fixed-point score is read multiplied by its scale and written divided by it.
+            Self::Nts((score, storage, path, magnif)) => {
+                let scale = codegen.scale(score);
+                let magnif = u32::try_from(scale)
+                    .ok()
+                    .and_then(|scale| magnif.checked_mul(scale))
+                    .ok_or(MCAsmError::MagnificationOverflow {
+                        magnification: *magnif,
+                        scale,
+                        span: score.span,
+                    })?;
+                Ok(vec![Qualified::from(
+                    storage.store_to_score(score, &path.path, magnif),
+                )])
+            }
+            Self::Stn((storage, path, score, magnif)) => {
+                Ok(vec![Qualified::from(score.storage_to_score(
+                    storage,
+                    &path.type_annotation,
+                    &path.path,
+                    *magnif / codegen.scale(score) as f32,
+                ))])
+            }
+
+            Self::Rel(score) => Ok(score.rel()),
+
+            Self::Label(_) | Self::Objective(_) | Self::Fixed(_) | Self::Long(_) => Ok(vec![]),
+            Self::Jmp(label) => Ok(vec![Qualified::from(format!(
+                "return run function {}",
+                codegen.function(&label.text)
+            ))]),
+
+            Self::If((disjunction, mnemonic)) => {
+                let mut res = Vec::new();
+                let mut conditions = Vec::new();
+                for conjunction in disjunction {
+                    let mut condition: Option<Condition> = None;
+                    for clause in conjunction {
+                        let (commands, other) = clause.condition(codegen)?;
+                        res.extend(commands);
+                        condition = Some(match condition {
+                            Some(condition) => condition.and(&other),
+                            None => other,
+                        });
+                    }
+                    conditions.extend(condition);
+                }
+                // `execute` has no OR, so each alternative raises a flag the guarded commands test.
+                let condition = match conditions.as_slice() {
+                    [condition] => condition.clone(),
+                    _ => {
+                        let flag = codegen.allocate();
+                        res.push(Qualified::from(flag.set(0)));
+                        res.extend(
+                            conditions
+                                .into_iter()
+                                .map(|condition| Qualified::from((flag.set(1), vec![condition]))),
+                        );
+                        flag.matches(false, &RangeLiteral::new(Some(1), Some(1), Span::default()))
+                    }
+                };
+                let flag = codegen.allocate();
+                let commands = mnemonic.to_qualified_in(codegen)?;
+                res.extend(guard(condition, commands, &flag));
+                Ok(res)
+            }
+
+            Self::Execute((modifier, mnemonic)) => {
+                let condition = modifier.condition();
+                Ok(mnemonic
+                    .to_qualified_in(codegen)?
                     .into_iter()
                     .map(|mut q| {
-                        q.push_condition(cmp_result.1.clone());
-                        println!("{:?}", q);
+                        q.push_condition(condition.clone());
                         q
                     })
-                    .collect::<Vec<Qualified>>(),
-            );
-            println!("{:?}", res);
-            Ok(res)
-        };
+                    .collect())
+            }
 
+            _ => match self.comparison() {
+                Some((unless, comparison, lhs, rhs, mnemonic)) => {
+                    let (mut res, condition) = compare(unless, comparison, lhs, rhs, codegen)?;
+                    let flag = codegen.allocate();
+                    let commands = mnemonic.to_qualified_in(codegen)?;
+                    res.extend(guard(condition, commands, &flag));
+                    Ok(res)
+                }
+                None => Ok(vec![]),
+            },
+        }
+    }
+}
+
+/// Written back as MCAssembly source, which parses into the same mnemonic.
+/// A conditional mnemonic guarding a jump is written with the shorthand of a lone label.
+impl std::fmt::Display for Mnemonic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let guarded = |mnemonic: &Mnemonic| match mnemonic {
+            Self::Jmp(label) => label.to_string(),
+            mnemonic => mnemonic.to_string(),
+        };
+        let jump = |f: &mut std::fmt::Formatter<'_>, name, (lhs, rhs, mnemonic): &Comparing| {
+            write!(f, "{} {} {} {}", name, lhs, rhs, guarded(mnemonic))
+        };
         match self {
-            Self::Def((score, assignable)) => assignable.assign(score),
-            Self::Mov((score, assignable)) => assignable.assign(score),
-
-            Self::Add((score, source)) => source.add(score),
-            Self::Sub((score, source)) => source.sub(score),
-            Self::Mul((score, source)) => source.mul(score),
-            Self::Div((score, source)) => source.div(score),
-            Self::Sur((score, source)) => source.sur(score),
-
-            Self::Nts((score, storage, path, magnif)) => Ok(vec![Qualified::from(
-                storage.store_to_score(score, &path.path, *magnif),
-            )]),
-            Self::Stn((storage, path, score, magnif)) => Ok(vec![Qualified::from(
-                score.storage_to_score(storage, &path.type_annotation, &path.path, *magnif),
-            )]),
-
-            Self::Rel(releasable) => Ok(releasable.rel()),
-
-            Self::Je((lhs, rhs, mnemonic)) => cmp(false, "=", lhs, rhs, mnemonic),
-            Self::Jne((lhs, rhs, mnemonic)) => cmp(true, "=", lhs, rhs, mnemonic),
-            Self::Jl((lhs, rhs, mnemonic)) => cmp(false, "<", lhs, rhs, mnemonic),
-            Self::Jg((lhs, rhs, mnemonic)) => cmp(false, ">", lhs, rhs, mnemonic),
+            Self::Def((score, source)) => write!(f, "DEF {} {}", score, source),
+            Self::Mov((score, source)) => write!(f, "MOV {} {}", score, source),
+            Self::Add((score, source)) => write!(f, "ADD {} {}", score, source),
+            Self::Sub((score, source)) => write!(f, "SUB {} {}", score, source),
+            Self::Mul((score, source)) => write!(f, "MUL {} {}", score, source),
+            Self::Div((score, source)) => write!(f, "DIV {} {}", score, source),
+            Self::Sur((score, source)) => write!(f, "SUR {} {}", score, source),
+            Self::Min((score, source)) => write!(f, "MIN {} {}", score, source),
+            Self::Max((score, source)) => write!(f, "MAX {} {}", score, source),
+            Self::Swp((score, other)) => write!(f, "SWP {} {}", score, other),
+            Self::Neg(score) => write!(f, "NEG {}", score),
+            Self::Abs(score) => write!(f, "ABS {}", score),
+            Self::Let((score, expression)) => write!(f, "LET {} = {}", score, expression),
+            Self::Nts((score, storage, path, magnif)) => {
+                write!(
+                    f,
+                    "NTS {} {} {} {}",
+                    score,
+                    storage.fullname(),
+                    path,
+                    magnif
+                )
+            }
+            Self::Stn((storage, path, score, magnif)) => {
+                write!(
+                    f,
+                    "STN {} {} {} {}",
+                    storage.fullname(),
+                    path,
+                    score,
+                    magnif
+                )
+            }
+            Self::Rel(score) => write!(f, "REL {}", score),
+            Self::Je(c) => jump(f, "JE", c),
+            Self::Jne(c) => jump(f, "JNE", c),
+            Self::Jl(c) => jump(f, "JL", c),
+            Self::Jg(c) => jump(f, "JG", c),
+            Self::Jle(c) => jump(f, "JLE", c),
+            Self::Jge(c) => jump(f, "JGE", c),
+            Self::Jin(c) => jump(f, "JIN", c),
+            Self::Jnl(c) => jump(f, "JNL", c),
+            Self::Jng(c) => jump(f, "JNG", c),
+            Self::Jnle(c) => jump(f, "JNLE", c),
+            Self::Jnge(c) => jump(f, "JNGE", c),
+            Self::Jnin(c) => jump(f, "JNIN", c),
+            Self::Label(label) => write!(f, "{}:", label),
+            Self::Jmp(label) => write!(f, "JMP {}", label),
+            Self::Objective(objective) => {
+                write!(f, ".objective {} {}", objective.name, objective.criteria)?;
+                match &objective.display_name {
+                    Some(display_name) => write!(f, " {}", display_name),
+                    None => Ok(()),
+                }
+            }
+            Self::If((disjunction, mnemonic)) => {
+                let condition = disjunction
+                    .iter()
+                    .map(|conjunction| {
+                        conjunction
+                            .iter()
+                            .map(Clause::to_string)
+                            .collect::<Vec<String>>()
+                            .join(" && ")
+                    })
+                    .collect::<Vec<String>>()
+                    .join(" || ");
+                write!(f, "IF {} THEN {}", condition, guarded(mnemonic))
+            }
+            Self::Execute((modifier, mnemonic)) => write!(f, "{} {}", modifier, mnemonic),
+            Self::Fixed((score, scale)) => write!(f, ".fixed {} {}", score, scale.value()),
+            Self::Long(score) => write!(f, ".long {}", score),
+            Self::And((score, source)) => write!(f, "AND {} {}", score, source),
+            Self::Or((score, source)) => write!(f, "OR {} {}", score, source),
+            Self::Xor((score, source)) => write!(f, "XOR {} {}", score, source),
+            Self::Not(score) => write!(f, "NOT {}", score),
+            Self::Shl((score, amount)) => write!(f, "SHL {} {}", score, amount),
+            Self::Shr((score, amount)) => write!(f, "SHR {} {}", score, amount),
         }
     }
 }
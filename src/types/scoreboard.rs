@@ -1,8 +1,8 @@
-use once_cell::sync::Lazy;
-
 use crate::{
-    Command, Condition, MCAsmError, Qualified, Releasable, ScoreAddable, ScoreAssignable,
-    ScoreDividable, ScoreMultiplicatable, ScoreSubtractable, ScoreSurplusable,
+    Codegen, Command, Condition, MCAsmError, Qualified, Releasable, ScoreAddable, ScoreAssignable,
+    ScoreDividable, ScoreHolder, ScoreMaximizable, ScoreMinimizable, ScoreMultiplicatable,
+    ScoreSubtractable, ScoreSurplusable, Span, Token,
+    types::{AsOperand, Comparison, IntLiteral, OperandRef, RangeLiteral},
     types::{
         opecode::ScoreCompareble,
         storage::{Storage, StorageType},
@@ -10,32 +10,69 @@ use crate::{
 };
 
 /// Objective::Scoreholder in Assembly
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Scoreboard {
     pub scoreholder: String,
     pub objective: String,
+    /// Where it's written, or the default for scoreboards the compiler introduces.
+    pub span: Span,
 }
 
-/// A special scoreboard to be used to literal score conversion.
-pub static LSC: Lazy<Scoreboard> = Lazy::new(|| Scoreboard {
-    scoreholder: "LITERAL_SCORE_CONVERSION".into(),
-    objective: "MC_ASM".into(),
-});
+/// How an objective is created, as declared by `.objective`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Objective {
+    pub name: Token,
+    pub criteria: String,
+    /// A text component, e.g. `"Money"` or `{"text":"Money","color":"gold"}`.
+    pub display_name: Option<String>,
+}
+
+impl Objective {
+    /// An objective nobody declared, which is created as `dummy`.
+    pub fn dummy(name: impl Into<String>) -> Self {
+        Self {
+            name: Token::new(name, Span::default()),
+            criteria: "dummy".to_string(),
+            display_name: None,
+        }
+    }
+    pub fn add(&self) -> Command {
+        Command::from(format!(
+            "scoreboard objectives add {} {}{}",
+            self.name,
+            self.criteria,
+            self.display_name
+                .as_ref()
+                .map(|d| format!(" {}", d))
+                .unwrap_or_default()
+        ))
+    }
+}
 
 impl Scoreboard {
+    /// The fake player holding a literal in the constant pool, e.g. `#c3 MC_ASM`.
+    pub fn constant(value: i32, objective: &str) -> Self {
+        Self::new(format!("#c{}", value), objective)
+    }
     pub fn new(scoreholder: impl Into<String>, objective: impl Into<String>) -> Self {
         Self {
             scoreholder: scoreholder.into(),
             objective: objective.into(),
+            span: Span::default(),
         }
     }
-    pub fn try_from(from: &str) -> Result<Self, MCAsmError> {
-        from.split_once("::")
-            .map(|(objective, scoreholder)| Self {
-                scoreholder: scoreholder.into(),
-                objective: objective.into(),
-            })
-            .ok_or(MCAsmError::InvalidScoreboard)
+    pub fn try_from(from: &Token) -> Result<Self, MCAsmError> {
+        let (objective, scoreholder) = from
+            .text
+            .split_once("::")
+            .filter(|(objective, scoreholder)| !objective.is_empty() && !scoreholder.is_empty())
+            .ok_or_else(|| MCAsmError::InvalidScoreboard(from.clone()))?;
+        ScoreHolder::parse(&from.slice(objective.len() + 2..from.text.len()))?;
+        Ok(Self {
+            scoreholder: scoreholder.into(),
+            objective: objective.into(),
+            span: from.span,
+        })
     }
     /// Unsafe!
     pub fn set(&self, source: i32) -> Command {
@@ -77,17 +114,64 @@ impl Scoreboard {
         ))
     }
     /// Unsafe!
-    pub fn compare(&self, is_unless: bool, comparison: &str, lhs: &Scoreboard) -> Condition {
+    pub fn compare(&self, is_unless: bool, comparison: Comparison, lhs: &Scoreboard) -> Condition {
         Condition::from(format!(
             "{} score {} {} {} {} {}",
             if is_unless { "unless" } else { "if" },
             lhs.scoreholder,
             lhs.objective,
-            comparison,
+            comparison.operator(),
             self.scoreholder,
             self.objective,
         ))
     }
+    pub fn matches(&self, is_unless: bool, range: &RangeLiteral) -> Condition {
+        Condition::from(format!(
+            "{} score {} {} matches {}",
+            if is_unless { "unless" } else { "if" },
+            self.scoreholder,
+            self.objective,
+            range,
+        ))
+    }
+    /// Multiplies the score by `numerator / denominator`, reduced so that nothing overflows needlessly.
+    pub fn scale_by(
+        &self,
+        numerator: i64,
+        denominator: i64,
+        codegen: &mut Codegen,
+    ) -> Vec<Qualified> {
+        let (mut a, mut b) = (numerator.abs(), denominator.abs());
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        let divisor = a.max(1);
+        let mut res = Vec::new();
+        for (operation, factor) in [("*=", numerator / divisor), ("/=", denominator / divisor)] {
+            if factor != 1 {
+                let constant = codegen.pooled(factor as i32);
+                res.push(Qualified::from(self.operate(operation, &constant)));
+            }
+        }
+        res
+    }
+    /// Operates with a source of another scale, through a register holding it converted.
+    fn operate_rescaled(
+        &self,
+        operation: &str,
+        source: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Vec<Qualified> {
+        let (from, to) = (codegen.scale(source), codegen.scale(self));
+        if from == to {
+            return vec![Qualified::from(self.operate(operation, source))];
+        }
+        let register = codegen.allocate();
+        let mut res = vec![Qualified::from(register.operate("=", source))];
+        res.extend(register.scale_by(to, from, codegen));
+        res.push(Qualified::from(self.operate(operation, &register)));
+        res
+    }
     pub fn get(&self) -> Command {
         Command::from(format!(
             "scoreboard players get {} {}",
@@ -112,39 +196,85 @@ impl Scoreboard {
     }
 }
 
+impl std::fmt::Display for Scoreboard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}::{}", self.objective, self.scoreholder)
+    }
+}
+
+impl AsOperand for Scoreboard {
+    fn as_operand(&self) -> OperandRef<'_> {
+        OperandRef::Score(self)
+    }
+}
+
 impl ScoreAssignable for Scoreboard {
-    fn assign(&self, other: &Scoreboard) -> Result<Vec<Qualified>, super::MCAsmError> {
-        Ok(vec![Qualified::from(other.operate("=", self))])
+    fn assign(
+        &self,
+        other: &Scoreboard,
+        codegen: &mut Codegen,
+    ) -> Result<Vec<Qualified>, MCAsmError> {
+        let mut res = vec![Qualified::from(other.operate("=", self))];
+        res.extend(other.scale_by(codegen.scale(other), codegen.scale(self), codegen));
+        Ok(res)
     }
 }
 
 impl ScoreAddable for Scoreboard {
-    fn add(&self, other: &Scoreboard) -> Result<Vec<Qualified>, super::MCAsmError> {
-        Ok(vec![Qualified::from(other.operate("+=", self))])
+    fn add(&self, other: &Scoreboard, codegen: &mut Codegen) -> Result<Vec<Qualified>, MCAsmError> {
+        Ok(other.operate_rescaled("+=", self, codegen))
     }
 }
 
 impl ScoreSubtractable for Scoreboard {
-    fn sub(&self, other: &Scoreboard) -> Result<Vec<Qualified>, super::MCAsmError> {
-        Ok(vec![Qualified::from(other.operate("-=", self))])
+    fn sub(&self, other: &Scoreboard, codegen: &mut Codegen) -> Result<Vec<Qualified>, MCAsmError> {
+        Ok(other.operate_rescaled("-=", self, codegen))
     }
 }
 
 impl ScoreMultiplicatable for Scoreboard {
-    fn mul(&self, other: &Scoreboard) -> Result<Vec<Qualified>, super::MCAsmError> {
-        Ok(vec![Qualified::from(other.operate("*=", self))])
+    /// The product carries both scales, so the source's one is divided out afterwards.
+    fn mul(&self, other: &Scoreboard, codegen: &mut Codegen) -> Result<Vec<Qualified>, MCAsmError> {
+        let mut res = vec![Qualified::from(other.operate("*=", self))];
+        res.extend(other.scale_by(1, codegen.scale(self), codegen));
+        Ok(res)
     }
 }
 
 impl ScoreDividable for Scoreboard {
-    fn div(&self, other: &Scoreboard) -> Result<Vec<Qualified>, super::MCAsmError> {
-        Ok(vec![Qualified::from(other.operate("/=", self))])
+    /// The quotient loses the source's scale, so it's multiplied in beforehand to keep precision.
+    /// A score divided by itself is copied first, as that would scale the source as well.
+    fn div(&self, other: &Scoreboard, codegen: &mut Codegen) -> Result<Vec<Qualified>, MCAsmError> {
+        let scale = codegen.scale(self);
+        let mut res = Vec::new();
+        let source = if scale != 1 && Codegen::key(self) == Codegen::key(other) {
+            let register = codegen.allocate();
+            res.push(Qualified::from(register.operate("=", self)));
+            register
+        } else {
+            self.clone()
+        };
+        res.extend(other.scale_by(scale, 1, codegen));
+        res.push(Qualified::from(other.operate("/=", &source)));
+        Ok(res)
     }
 }
 
 impl ScoreSurplusable for Scoreboard {
-    fn sur(&self, other: &Scoreboard) -> Result<Vec<Qualified>, super::MCAsmError> {
-        Ok(vec![Qualified::from(other.operate("%=", self))])
+    fn sur(&self, other: &Scoreboard, codegen: &mut Codegen) -> Result<Vec<Qualified>, MCAsmError> {
+        Ok(other.operate_rescaled("%=", self, codegen))
+    }
+}
+
+impl ScoreMinimizable for Scoreboard {
+    fn min(&self, other: &Scoreboard, codegen: &mut Codegen) -> Result<Vec<Qualified>, MCAsmError> {
+        Ok(other.operate_rescaled("<", self, codegen))
+    }
+}
+
+impl ScoreMaximizable for Scoreboard {
+    fn max(&self, other: &Scoreboard, codegen: &mut Codegen) -> Result<Vec<Qualified>, MCAsmError> {
+        Ok(other.operate_rescaled(">", self, codegen))
     }
 }
 
@@ -155,12 +285,113 @@ impl Releasable for Scoreboard {
 }
 
 impl ScoreCompareble for Scoreboard {
+    /// Scores of different scales are compared by their values. With the scales reduced to `l` and `r`,
+    /// `lhs / l` against `self / r` is decided by the quotients, and when they are equal,
+    /// by the remainders brought to the denominator `l * r`, so that nothing can overflow.
     fn cmp(
         &self,
         unless: bool,
-        comparison: &str,
+        comparison: Comparison,
         lhs: &Scoreboard,
+        codegen: &mut Codegen,
     ) -> Result<(Vec<Qualified>, Condition), MCAsmError> {
-        Ok((vec![], lhs.compare(unless, comparison, self)))
+        let scales = (codegen.scale(lhs), codegen.scale(self));
+        if scales.0 == scales.1 {
+            return Ok((vec![], self.compare(unless, comparison, lhs)));
+        }
+        let (mut a, mut b) = scales;
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        let (l, r) = (scales.0 / a, scales.1 / a);
+        if l.checked_mul(r)
+            .is_none_or(|product| product > i64::from(i32::MAX))
+        {
+            return Err(MCAsmError::IncomparableScales {
+                scales,
+                span: self.span,
+            });
+        }
+        // The quotient and the remainder of a side, or the score itself and no remainder when its share is 1.
+        // An unset score is only copied when it's set, as copying it would set it to 0.
+        let set = RangeLiteral::new(Some(i32::MIN.into()), None, Span::default());
+        let mut res = Vec::new();
+        let mut guards = Vec::new();
+        let mut split = |score: &Scoreboard, share: i64, other: i64, codegen: &mut Codegen| {
+            if share == 1 {
+                return (score.clone(), None);
+            }
+            let (quotient, remainder) = (codegen.allocate(), codegen.allocate());
+            let (mut commands, divisor) = IntLiteral::to_score(share as i32, codegen);
+            commands.extend([
+                Qualified::from(quotient.operate("=", score)),
+                Qualified::from(quotient.operate("/=", &divisor)),
+                Qualified::from(remainder.operate("=", score)),
+                Qualified::from(remainder.operate("%=", &divisor)),
+            ]);
+            if other != 1 {
+                let (set_factor, factor) = IntLiteral::to_score(other as i32, codegen);
+                commands.extend(set_factor);
+                commands.push(Qualified::from(remainder.operate("*=", &factor)));
+            }
+            if score.objective != codegen.objective {
+                let guard = score.matches(false, &set);
+                for command in &mut commands {
+                    command.push_condition(guard.clone());
+                }
+                guards.push(guard);
+            }
+            res.extend(commands);
+            (quotient, Some(remainder))
+        };
+        let (lhs_quotient, lhs_remainder) = split(lhs, l, r, codegen);
+        let (rhs_quotient, rhs_remainder) = split(self, r, l, codegen);
+        // A remainder is at least 0, so comparing it with none is matching it against a range.
+        let zero = |comparison: Comparison| {
+            let (min, max) = match comparison {
+                Comparison::Lt => (None, Some(-1)),
+                Comparison::Le => (None, Some(0)),
+                Comparison::Eq | Comparison::In => (Some(0), Some(0)),
+                Comparison::Ge => (Some(0), None),
+                Comparison::Gt => (Some(1), None),
+            };
+            RangeLiteral::new(min, max, Span::default())
+        };
+        let remainders = match (&lhs_remainder, &rhs_remainder) {
+            (Some(lhs), Some(rhs)) => rhs.compare(false, comparison, lhs),
+            (Some(lhs), None) => lhs.matches(false, &zero(comparison)),
+            (None, Some(rhs)) => {
+                let converse = match comparison {
+                    Comparison::Lt => Comparison::Gt,
+                    Comparison::Le => Comparison::Ge,
+                    Comparison::Gt => Comparison::Lt,
+                    Comparison::Ge => Comparison::Le,
+                    comparison => comparison,
+                };
+                rhs.matches(false, &zero(converse))
+            }
+            (None, None) => unreachable!("different scales don't both reduce to 1"),
+        };
+        let flag = codegen.allocate();
+        res.push(Qualified::from(flag.set(0)));
+        let strict = match comparison {
+            Comparison::Lt | Comparison::Le => Some(Comparison::Lt),
+            Comparison::Gt | Comparison::Ge => Some(Comparison::Gt),
+            Comparison::Eq | Comparison::In => None,
+        };
+        if let Some(strict) = strict {
+            let quotients = rhs_quotient.compare(false, strict, &lhs_quotient);
+            res.push(Qualified::from((
+                flag.set(1),
+                [guards.clone(), vec![quotients]].concat(),
+            )));
+        }
+        let equal = rhs_quotient.compare(false, Comparison::Eq, &lhs_quotient);
+        res.push(Qualified::from((
+            flag.set(1),
+            [guards, vec![equal, remainders]].concat(),
+        )));
+        let holds = RangeLiteral::new(Some(1), Some(1), Span::default());
+        Ok((res, flag.matches(unless, &holds)))
     }
 }
@@ -0,0 +1,209 @@
+use crate::types::*;
+
+/// Names which may appear inside the brackets of a target selector.
+const ARGUMENTS: [&str; 21] = [
+    "x",
+    "y",
+    "z",
+    "distance",
+    "dx",
+    "dy",
+    "dz",
+    "scores",
+    "tag",
+    "team",
+    "limit",
+    "sort",
+    "level",
+    "gamemode",
+    "name",
+    "x_rotation",
+    "y_rotation",
+    "type",
+    "nbt",
+    "advancements",
+    "predicate",
+];
+
+/// Arguments which mean something even when their value is empty, e.g. `tag=` for no tags.
+const EMPTY_ALLOWED: [&str; 3] = ["tag", "team", "name"];
+
+/// A target selector, e.g. `@e[type=zombie,limit=1]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Selector {
+    /// `p`, `a`, `r`, `s`, `e` or `n`.
+    pub variable: char,
+    /// Each argument as written, `!` of negation included in the value.
+    pub arguments: Vec<(String, String)>,
+}
+
+/// Byte offsets of the commas splitting the arguments, skipping the ones nested or quoted.
+fn top_level_commas(text: &str) -> Result<Vec<usize>, (usize, &'static str)> {
+    let mut commas = Vec::new();
+    let mut depth = Vec::new();
+    let mut quote = None;
+    let mut escaped = false;
+    for (offset, c) in text.char_indices() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some((q, _)) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' => quote = Some((c, offset)),
+                '[' | '{' => depth.push((c, offset)),
+                ']' | '}' => {
+                    let open = if c == ']' { '[' } else { '{' };
+                    if depth.pop().map(|(c, _)| c) != Some(open) {
+                        return Err((offset, "balanced brackets"));
+                    }
+                }
+                ',' if depth.is_empty() => commas.push(offset),
+                _ => {}
+            },
+        }
+    }
+    match (quote, depth.last()) {
+        (Some((_, offset)), _) => Err((offset, "a closing quote")),
+        (None, Some((_, offset))) => Err((*offset, "balanced brackets")),
+        (None, None) => Ok(commas),
+    }
+}
+
+impl Selector {
+    pub fn parse(token: &Token) -> Result<Self, MCAsmError> {
+        let invalid =
+            |expected: &'static str, found: Token| MCAsmError::InvalidSelector { expected, found };
+        let variable = token
+            .text
+            .strip_prefix('@')
+            .and_then(|rest| rest.chars().next())
+            .filter(|c| matches!(c, 'p' | 'a' | 'r' | 's' | 'e' | 'n'))
+            .ok_or_else(|| {
+                let end = token
+                    .text
+                    .char_indices()
+                    .nth(2)
+                    .map_or(token.text.len(), |(offset, _)| offset);
+                invalid(
+                    "one of `@p`, `@a`, `@r`, `@s`, `@e` or `@n`",
+                    token.slice(0..end),
+                )
+            })?;
+        let rest = &token.text[2..];
+        if rest.is_empty() {
+            return Ok(Self {
+                variable,
+                arguments: Vec::new(),
+            });
+        }
+        if !rest.starts_with('[') {
+            return Err(invalid("`[`", token.slice(2..token.text.len())));
+        }
+        if !rest.ends_with(']') {
+            return Err(invalid("`]`", token.clone()));
+        }
+        let inner = token.slice(3..token.text.len() - 1);
+        let commas = top_level_commas(&inner.text).map_err(|(offset, expected)| {
+            invalid(
+                expected,
+                inner.slice(offset.min(inner.text.len())..inner.text.len()),
+            )
+        })?;
+        if inner.text.trim().is_empty() {
+            return Ok(Self {
+                variable,
+                arguments: Vec::new(),
+            });
+        }
+
+        let mut arguments = Vec::new();
+        let bounds = std::iter::once(0)
+            .chain(commas.iter().map(|comma| comma + 1))
+            .zip(
+                commas
+                    .iter()
+                    .copied()
+                    .chain(std::iter::once(inner.text.len())),
+            );
+        for (start, end) in bounds {
+            let argument = inner.slice(start..end);
+            let (key, value) = argument
+                .text
+                .split_once('=')
+                .ok_or_else(|| invalid("`key=value`", argument.clone()))?;
+            let leading = key.len() - key.trim_start().len();
+            let (key, value) = (key.trim(), value.trim());
+            let key_token = argument.slice(leading..leading + key.len());
+            if !ARGUMENTS.contains(&key) {
+                return Err(invalid(
+                    "a selector argument like `tag` or `type`",
+                    key_token,
+                ));
+            }
+            if value.trim_start_matches('!').is_empty() && !EMPTY_ALLOWED.contains(&key) {
+                return Err(invalid("a value", argument));
+            }
+            arguments.push((key.to_string(), value.to_string()));
+        }
+        Ok(Self {
+            variable,
+            arguments,
+        })
+    }
+}
+
+impl std::fmt::Display for Selector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "@{}", self.variable)?;
+        if !self.arguments.is_empty() {
+            let arguments = self
+                .arguments
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect::<Vec<String>>();
+            write!(f, "[{}]", arguments.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// Whose score it is, as written after `::` of a scoreboard.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScoreHolder {
+    /// `*`, every holder tracked by the objective.
+    Wildcard,
+    /// A name starting with `#`, which never shows up on the sidebar.
+    FakePlayer(String),
+    Player(String),
+    Selector(Selector),
+}
+
+impl ScoreHolder {
+    pub fn parse(token: &Token) -> Result<Self, MCAsmError> {
+        let text = token.text.as_str();
+        if text.starts_with('@') {
+            return Selector::parse(token).map(Self::Selector);
+        }
+        let is_valid = |c: char| !c.is_whitespace() && !"[]{}\"'@".contains(c);
+        match text {
+            "*" => Ok(Self::Wildcard),
+            _ if text.is_empty() || !text.chars().all(is_valid) => {
+                Err(MCAsmError::InvalidScoreHolder(token.clone()))
+            }
+            _ if text.starts_with('#') && text.len() > 1 => Ok(Self::FakePlayer(text.into())),
+            _ if text.starts_with('#') => Err(MCAsmError::InvalidScoreHolder(token.clone())),
+            _ => Ok(Self::Player(text.into())),
+        }
+    }
+}
+
+impl std::fmt::Display for ScoreHolder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Wildcard => write!(f, "*"),
+            Self::FakePlayer(name) | Self::Player(name) => write!(f, "{}", name),
+            Self::Selector(selector) => write!(f, "{}", selector),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+/// Where something is in the source: a zero-based line index and a byte range within that line.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(line: usize, start: usize, end: usize) -> Self {
+        Self { line, start, end }
+    }
+    /// A span covering both `self` and `other`, assuming they are on the same line.
+    pub fn to(&self, other: &Span) -> Span {
+        Span {
+            line: self.line,
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+}
+
+/// A whitespace-separated word of a line, remembering where it came from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token {
+    pub text: String,
+    pub span: Span,
+}
+
+impl Token {
+    pub fn new(text: impl Into<String>, span: Span) -> Self {
+        Self {
+            text: text.into(),
+            span,
+        }
+    }
+    /// Cuts out a part of the token, keeping the span pointing at the right columns.
+    pub fn slice(&self, range: std::ops::Range<usize>) -> Token {
+        Token {
+            text: self.text[range.clone()].to_string(),
+            span: Span::new(
+                self.span.line,
+                self.span.start + range.start,
+                self.span.start + range.end,
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
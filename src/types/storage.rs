@@ -1,6 +1,7 @@
 use crate::types::*;
 use regex::Regex;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum StorageType {
     Byte,
     Short,
@@ -28,7 +29,7 @@ impl std::fmt::Display for StorageType {
 }
 
 impl StorageType {
-    fn try_from(from: &str) -> Option<Self> {
+    pub(crate) fn try_from(from: &str) -> Option<Self> {
         match from {
             "byte" => Some(Self::Byte),
             "short" => Some(Self::Short),
@@ -41,15 +42,16 @@ impl StorageType {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct Storage {
     pub namespace: String,
     pub name: String,
 }
 
 impl Storage {
-    pub fn try_from(from: &str) -> Result<Self, MCAsmError> {
-        let re = Regex::new(r"([a-z0-9\_\-\.]+):([a-z0-9\_\-\.]+)").unwrap();
-        re.captures(from)
+    pub fn try_from(from: &Token) -> Result<Self, MCAsmError> {
+        let re = Regex::new(r"^([a-z0-9\_\-\.]+):([a-z0-9\_\-\.]+)$").unwrap();
+        re.captures(&from.text)
             .and_then(|caps| {
                 if let (Some(namespace), Some(name)) = (caps.get(1), caps.get(2)) {
                     Some(Self {
@@ -60,7 +62,7 @@ impl Storage {
                     None
                 }
             })
-            .ok_or(MCAsmError::InvalidStorage)
+            .ok_or_else(|| MCAsmError::InvalidStorage(from.clone()))
     }
     pub fn fullname(&self) -> String {
         format!("{}:{}", self.namespace, self.name)
@@ -72,7 +74,7 @@ impl Storage {
         magnification: u32,
     ) -> Command {
         Command::from(format!(
-            "execute store result score {} {} run data get {} {} {}",
+            "execute store result score {} {} run data get storage {} {} {}",
             scoreboard.scoreholder,
             scoreboard.objective,
             self.fullname(),
@@ -82,26 +84,34 @@ impl Storage {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct Path {
     pub path: String,
     pub type_annotation: StorageType,
 }
 
 impl Path {
-    pub fn try_from(from: &str) -> Result<Self, MCAsmError> {
-        if let Some((path, type_annotation)) = from.rsplit_once("::<") {
+    pub fn try_from(from: &Token) -> Result<Self, MCAsmError> {
+        if let Some(index) = from.text.rfind("::<") {
+            let annotation = from.slice(index + 3..from.text.len());
+            let trimmed = annotation.text.trim_end_matches(">");
             Ok(Self {
-                path: path.to_string(),
-                type_annotation: {
-                    let trimmed = type_annotation.trim_end_matches(">");
-                    StorageType::try_from(trimmed).ok_or(MCAsmError::UnknownType)?
-                },
+                path: from.text[..index].to_string(),
+                type_annotation: StorageType::try_from(trimmed)
+                    .ok_or_else(|| MCAsmError::UnknownType(annotation.slice(0..trimmed.len())))?,
             })
         } else {
             Ok(Self {
-                path: from.to_string(),
+                path: from.text.clone(),
                 type_annotation: StorageType::Int,
             })
         }
     }
 }
+
+/// Written as in the source, always with the type annotation.
+impl std::fmt::Display for Path {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}::<{}>", self.path, self.type_annotation)
+    }
+}
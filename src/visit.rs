@@ -0,0 +1,302 @@
+//! Walking over parsed mnemonics, for tools like linters and formatters.
+//!
+//! A `Visitor` looks at every node by reference, and a `Fold` rebuilds them by value.
+//! Each method defaults to its `walk_*` or `fold_*` function, which goes into the children,
+//! so an implementation overrides the nodes it cares about and calls the function to keep going.
+
+use crate::types::*;
+
+pub trait Visitor<'ast> {
+    fn visit_program(&mut self, program: &'ast [Mnemonic]) {
+        for mnemonic in program {
+            self.visit_mnemonic(mnemonic);
+        }
+    }
+    fn visit_mnemonic(&mut self, mnemonic: &'ast Mnemonic) {
+        walk_mnemonic(self, mnemonic);
+    }
+    fn visit_clause(&mut self, clause: &'ast Clause) {
+        walk_clause(self, clause);
+    }
+    fn visit_operand(&mut self, operand: &'ast Operand) {
+        walk_operand(self, operand);
+    }
+    fn visit_expression(&mut self, expression: &'ast Expression) {
+        walk_expression(self, expression);
+    }
+    fn visit_modifier(&mut self, modifier: &'ast Modifier) {
+        walk_modifier(self, modifier);
+    }
+    fn visit_scoreboard(&mut self, _scoreboard: &'ast Scoreboard) {}
+    /// A label defined or jumped to.
+    fn visit_label(&mut self, _label: &'ast Token) {}
+}
+
+pub fn walk_mnemonic<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, mnemonic: &'ast Mnemonic) {
+    match mnemonic {
+        Mnemonic::Def((score, source))
+        | Mnemonic::Mov((score, source))
+        | Mnemonic::Add((score, source))
+        | Mnemonic::Sub((score, source))
+        | Mnemonic::Mul((score, source))
+        | Mnemonic::Div((score, source))
+        | Mnemonic::Sur((score, source))
+        | Mnemonic::Min((score, source))
+        | Mnemonic::Max((score, source))
+        | Mnemonic::And((score, source))
+        | Mnemonic::Or((score, source))
+        | Mnemonic::Xor((score, source))
+        | Mnemonic::Shl((score, source))
+        | Mnemonic::Shr((score, source)) => {
+            visitor.visit_scoreboard(score);
+            visitor.visit_operand(source);
+        }
+        Mnemonic::Swp((score, other)) => {
+            visitor.visit_scoreboard(score);
+            visitor.visit_scoreboard(other);
+        }
+        Mnemonic::Neg(score)
+        | Mnemonic::Abs(score)
+        | Mnemonic::Not(score)
+        | Mnemonic::Rel(score)
+        | Mnemonic::Long(score)
+        | Mnemonic::Fixed((score, _))
+        | Mnemonic::Nts((score, ..))
+        | Mnemonic::Stn((_, _, score, _)) => visitor.visit_scoreboard(score),
+        Mnemonic::Let((score, expression)) => {
+            visitor.visit_scoreboard(score);
+            visitor.visit_expression(expression);
+        }
+        Mnemonic::Label(label) | Mnemonic::Jmp(label) => visitor.visit_label(label),
+        Mnemonic::If((disjunction, guarded)) => {
+            for clause in disjunction.iter().flatten() {
+                visitor.visit_clause(clause);
+            }
+            visitor.visit_mnemonic(guarded);
+        }
+        Mnemonic::Execute((modifier, wrapped)) => {
+            visitor.visit_modifier(modifier);
+            visitor.visit_mnemonic(wrapped);
+        }
+        Mnemonic::Je((lhs, rhs, guarded))
+        | Mnemonic::Jne((lhs, rhs, guarded))
+        | Mnemonic::Jl((lhs, rhs, guarded))
+        | Mnemonic::Jg((lhs, rhs, guarded))
+        | Mnemonic::Jle((lhs, rhs, guarded))
+        | Mnemonic::Jge((lhs, rhs, guarded))
+        | Mnemonic::Jin((lhs, rhs, guarded))
+        | Mnemonic::Jnl((lhs, rhs, guarded))
+        | Mnemonic::Jng((lhs, rhs, guarded))
+        | Mnemonic::Jnle((lhs, rhs, guarded))
+        | Mnemonic::Jnge((lhs, rhs, guarded))
+        | Mnemonic::Jnin((lhs, rhs, guarded)) => {
+            visitor.visit_scoreboard(lhs);
+            visitor.visit_operand(rhs);
+            visitor.visit_mnemonic(guarded);
+        }
+        Mnemonic::Objective(_) => {}
+    }
+}
+
+pub fn walk_clause<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, clause: &'ast Clause) {
+    visitor.visit_scoreboard(&clause.lhs);
+    visitor.visit_operand(&clause.rhs);
+}
+
+pub fn walk_operand<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, operand: &'ast Operand) {
+    match operand {
+        Operand::Score(score) => visitor.visit_scoreboard(score),
+        Operand::ScoreRange(range) => {
+            for score in range.scoreboards() {
+                visitor.visit_scoreboard(score);
+            }
+        }
+        Operand::Int(_) | Operand::Float(_) | Operand::Range(_) => {}
+    }
+}
+
+pub fn walk_expression<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    expression: &'ast Expression,
+) {
+    match expression {
+        Expression::Score(score) => visitor.visit_scoreboard(score),
+        Expression::Int(_) => {}
+        Expression::Negate(negated) => visitor.visit_expression(negated),
+        Expression::Binary((lhs, _, rhs)) => {
+            visitor.visit_expression(lhs);
+            visitor.visit_expression(rhs);
+        }
+    }
+}
+
+pub fn walk_modifier<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, modifier: &'ast Modifier) {
+    if let Some(score) = modifier.scoreboard() {
+        visitor.visit_scoreboard(score);
+    }
+}
+
+pub trait Fold {
+    fn fold_program(&mut self, program: Vec<Mnemonic>) -> Vec<Mnemonic> {
+        program
+            .into_iter()
+            .map(|mnemonic| self.fold_mnemonic(mnemonic))
+            .collect()
+    }
+    fn fold_mnemonic(&mut self, mnemonic: Mnemonic) -> Mnemonic {
+        fold_mnemonic(self, mnemonic)
+    }
+    fn fold_clause(&mut self, clause: Clause) -> Clause {
+        fold_clause(self, clause)
+    }
+    fn fold_operand(&mut self, operand: Operand) -> Operand {
+        fold_operand(self, operand)
+    }
+    fn fold_expression(&mut self, expression: Expression) -> Expression {
+        fold_expression(self, expression)
+    }
+    fn fold_modifier(&mut self, modifier: Modifier) -> Modifier {
+        fold_modifier(self, modifier)
+    }
+    fn fold_scoreboard(&mut self, scoreboard: Scoreboard) -> Scoreboard {
+        scoreboard
+    }
+    /// A label defined or jumped to.
+    fn fold_label(&mut self, label: Token) -> Token {
+        label
+    }
+}
+
+pub fn fold_mnemonic<F: Fold + ?Sized>(folder: &mut F, mnemonic: Mnemonic) -> Mnemonic {
+    let mut sourced = |(score, source): (Scoreboard, Operand)| {
+        (folder.fold_scoreboard(score), folder.fold_operand(source))
+    };
+    match mnemonic {
+        Mnemonic::Def(operands) => Mnemonic::Def(sourced(operands)),
+        Mnemonic::Mov(operands) => Mnemonic::Mov(sourced(operands)),
+        Mnemonic::Add(operands) => Mnemonic::Add(sourced(operands)),
+        Mnemonic::Sub(operands) => Mnemonic::Sub(sourced(operands)),
+        Mnemonic::Mul(operands) => Mnemonic::Mul(sourced(operands)),
+        Mnemonic::Div(operands) => Mnemonic::Div(sourced(operands)),
+        Mnemonic::Sur(operands) => Mnemonic::Sur(sourced(operands)),
+        Mnemonic::Min(operands) => Mnemonic::Min(sourced(operands)),
+        Mnemonic::Max(operands) => Mnemonic::Max(sourced(operands)),
+        Mnemonic::And(operands) => Mnemonic::And(sourced(operands)),
+        Mnemonic::Or(operands) => Mnemonic::Or(sourced(operands)),
+        Mnemonic::Xor(operands) => Mnemonic::Xor(sourced(operands)),
+        Mnemonic::Shl(operands) => Mnemonic::Shl(sourced(operands)),
+        Mnemonic::Shr(operands) => Mnemonic::Shr(sourced(operands)),
+        Mnemonic::Swp((score, other)) => {
+            Mnemonic::Swp((folder.fold_scoreboard(score), folder.fold_scoreboard(other)))
+        }
+        Mnemonic::Neg(score) => Mnemonic::Neg(folder.fold_scoreboard(score)),
+        Mnemonic::Abs(score) => Mnemonic::Abs(folder.fold_scoreboard(score)),
+        Mnemonic::Not(score) => Mnemonic::Not(folder.fold_scoreboard(score)),
+        Mnemonic::Rel(score) => Mnemonic::Rel(folder.fold_scoreboard(score)),
+        Mnemonic::Long(score) => Mnemonic::Long(folder.fold_scoreboard(score)),
+        Mnemonic::Fixed((score, scale)) => Mnemonic::Fixed((folder.fold_scoreboard(score), scale)),
+        Mnemonic::Let((score, expression)) => Mnemonic::Let((
+            folder.fold_scoreboard(score),
+            folder.fold_expression(expression),
+        )),
+        Mnemonic::Nts((score, storage, path, magnif)) => {
+            Mnemonic::Nts((folder.fold_scoreboard(score), storage, path, magnif))
+        }
+        Mnemonic::Stn((storage, path, score, magnif)) => {
+            Mnemonic::Stn((storage, path, folder.fold_scoreboard(score), magnif))
+        }
+        Mnemonic::Je(c) => Mnemonic::Je(fold_comparing(folder, c)),
+        Mnemonic::Jne(c) => Mnemonic::Jne(fold_comparing(folder, c)),
+        Mnemonic::Jl(c) => Mnemonic::Jl(fold_comparing(folder, c)),
+        Mnemonic::Jg(c) => Mnemonic::Jg(fold_comparing(folder, c)),
+        Mnemonic::Jle(c) => Mnemonic::Jle(fold_comparing(folder, c)),
+        Mnemonic::Jge(c) => Mnemonic::Jge(fold_comparing(folder, c)),
+        Mnemonic::Jin(c) => Mnemonic::Jin(fold_comparing(folder, c)),
+        Mnemonic::Jnl(c) => Mnemonic::Jnl(fold_comparing(folder, c)),
+        Mnemonic::Jng(c) => Mnemonic::Jng(fold_comparing(folder, c)),
+        Mnemonic::Jnle(c) => Mnemonic::Jnle(fold_comparing(folder, c)),
+        Mnemonic::Jnge(c) => Mnemonic::Jnge(fold_comparing(folder, c)),
+        Mnemonic::Jnin(c) => Mnemonic::Jnin(fold_comparing(folder, c)),
+        Mnemonic::Label(label) => Mnemonic::Label(folder.fold_label(label)),
+        Mnemonic::Jmp(label) => Mnemonic::Jmp(folder.fold_label(label)),
+        Mnemonic::Objective(objective) => Mnemonic::Objective(objective),
+        Mnemonic::If((disjunction, guarded)) => Mnemonic::If((
+            disjunction
+                .into_iter()
+                .map(|conjunction| {
+                    conjunction
+                        .into_iter()
+                        .map(|clause| folder.fold_clause(clause))
+                        .collect()
+                })
+                .collect(),
+            Box::new(folder.fold_mnemonic(*guarded)),
+        )),
+        Mnemonic::Execute((modifier, wrapped)) => Mnemonic::Execute((
+            folder.fold_modifier(modifier),
+            Box::new(folder.fold_mnemonic(*wrapped)),
+        )),
+    }
+}
+
+fn fold_comparing<F: Fold + ?Sized>(folder: &mut F, (lhs, rhs, guarded): Comparing) -> Comparing {
+    (
+        folder.fold_scoreboard(lhs),
+        folder.fold_operand(rhs),
+        Box::new(folder.fold_mnemonic(*guarded)),
+    )
+}
+
+pub fn fold_clause<F: Fold + ?Sized>(folder: &mut F, clause: Clause) -> Clause {
+    Clause {
+        lhs: folder.fold_scoreboard(clause.lhs),
+        rhs: folder.fold_operand(clause.rhs),
+        ..clause
+    }
+}
+
+pub fn fold_operand<F: Fold + ?Sized>(folder: &mut F, operand: Operand) -> Operand {
+    match operand {
+        Operand::Score(score) => Operand::Score(folder.fold_scoreboard(score)),
+        Operand::ScoreRange(range) => {
+            let mut bound = |bound: Option<&Bound>| {
+                bound.cloned().map(|bound| match bound {
+                    Bound::Score(score) => Bound::Score(folder.fold_scoreboard(score)),
+                    int => int,
+                })
+            };
+            let min = bound(range.min());
+            let max = bound(range.max());
+            Operand::ScoreRange(ScoreRange::new(min, max, range.span()))
+        }
+        literal => literal,
+    }
+}
+
+pub fn fold_expression<F: Fold + ?Sized>(folder: &mut F, expression: Expression) -> Expression {
+    match expression {
+        Expression::Score(score) => Expression::Score(folder.fold_scoreboard(score)),
+        Expression::Int(int) => Expression::Int(int),
+        Expression::Negate(negated) => {
+            Expression::Negate(Box::new(folder.fold_expression(*negated)))
+        }
+        Expression::Binary((lhs, operator, rhs)) => Expression::Binary((
+            Box::new(folder.fold_expression(*lhs)),
+            operator,
+            Box::new(folder.fold_expression(*rhs)),
+        )),
+    }
+}
+
+pub fn fold_modifier<F: Fold + ?Sized>(folder: &mut F, modifier: Modifier) -> Modifier {
+    match modifier {
+        Modifier::Store {
+            success,
+            target: StoreTarget::Score(score),
+        } => Modifier::Store {
+            success,
+            target: StoreTarget::Score(folder.fold_scoreboard(score)),
+        },
+        modifier => modifier,
+    }
+}